@@ -8,9 +8,10 @@ def fib(n):
 
 def fib_list(n):
     result = []
-    while n >= 0:
-        result = [fib(n)] + result
-        n = n - 1
+    i = 0
+    while i <= n:
+        result.append(fib(i))
+        i = i + 1
     return result
 
 print(fib_list(9))
@@ -25,11 +25,7 @@ state = initial_state
 max_steps = 1000
 
 steps = 0
-while state != accept_state and state != reject_state:
-    if steps > max_steps:
-        print("Maximum steps exceeded")
-        return
-
+while state not in [accept_state, reject_state] and steps <= max_steps:
     # figure out current symbol
     if head_pos < tape_len and head_pos >= 0:
         current_symbol = tape[head_pos]
@@ -40,7 +36,9 @@ while state != accept_state and state != reject_state:
     step_res = find_trans()
     if step_res == 1:
         print("No valid transition found")
-        return
+        new_state = reject_state
+        new_symbol = current_symbol
+        direction = "N"
     else:
         new_state = step_res[0]
         new_symbol = step_res[1]
@@ -73,4 +71,6 @@ if state == accept_state:
     print(tape)
     print("Accepted!")
 else:
+    if steps > max_steps:
+        print("Maximum steps exceeded")
     print("Rejected!")
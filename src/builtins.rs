@@ -0,0 +1,302 @@
+// functions that can be called without being defined, e.g. ord("a")
+// user-defined functions with the same name shadow them
+
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+use crate::{
+    common::{ast::Name, py_error::PyError},
+    file::File,
+    interpreter::{Interpreter, Value},
+};
+
+pub type Builtin = fn(&mut Interpreter, Args, &Name) -> Result<Value, PyError>;
+
+const BUILTINS: &[(&str, Builtin)] = &[
+    ("print", print),
+    ("input", input),
+    ("open", open),
+    ("ord", ord),
+    ("chr", chr),
+    ("str", str),
+    ("repr", repr),
+];
+
+pub fn lookup(name: &str) -> Option<(&'static str, Builtin)> {
+    BUILTINS.iter().find(|(n, _)| *n == name).copied()
+}
+
+// arguments of a call to a builtin function or method
+pub struct Args {
+    pub positional: Vec<Value>,
+    pub keyword: Vec<(String, Value)>,
+}
+
+impl Args {
+    // removes a keyword argument, the ones that are left over are invalid
+    pub fn take_keyword(&mut self, key: &str) -> Option<Value> {
+        let i = self.keyword.iter().position(|(k, _)| k == key)?;
+        Some(self.keyword.remove(i).1)
+    }
+
+    // fails on the keyword arguments that are left after taking the valid ones
+    pub fn reject_remaining(&self, func: &str, name: &Name) -> Result<(), PyError> {
+        match self.keyword.first() {
+            Some((k, _)) => Err(error(
+                format!("TypeError: '{k}' is an invalid keyword argument for {func}()"),
+                name,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // for the builtins that only take positional arguments
+    pub fn positional_only(self, func: &str, name: &Name) -> Result<Vec<Value>, PyError> {
+        if self.keyword.is_empty() {
+            Ok(self.positional)
+        } else {
+            Err(error(
+                format!("TypeError: {func}() takes no keyword arguments"),
+                name,
+            ))
+        }
+    }
+}
+
+// error pointing at the name of the called function or method
+pub fn error(msg: String, name: &Name) -> PyError {
+    PyError {
+        msg,
+        line: name.line,
+        column: name.column,
+    }
+}
+
+// checks that a builtin function or method got between min and max arguments
+pub fn check_arity(
+    func: &str,
+    name: &Name,
+    args: &[Value],
+    min: usize,
+    max: usize,
+) -> Result<(), PyError> {
+    let n = args.len();
+    if min <= n && n <= max {
+        return Ok(());
+    }
+    let plural = |x: usize| if x == 1 { "" } else { "s" };
+    let msg = if max == 0 {
+        format!("TypeError: {func}() takes no arguments ({n} given)")
+    } else if min == max {
+        format!(
+            "TypeError: {func}() takes exactly {min} argument{} ({n} given)",
+            plural(min)
+        )
+    } else if n < min {
+        format!(
+            "TypeError: {func}() expected at least {min} argument{}, got {n}",
+            plural(min)
+        )
+    } else {
+        format!(
+            "TypeError: {func}() expected at most {max} argument{}, got {n}",
+            plural(max)
+        )
+    };
+    Err(error(msg, name))
+}
+
+// print(*objects, sep=' ', end='\n', file=None, flush=False)
+fn print(int: &mut Interpreter, mut args: Args, name: &Name) -> Result<Value, PyError> {
+    let sep = str_keyword(&mut args, "sep", " ", name)?;
+    let end = str_keyword(&mut args, "end", "\n", name)?;
+    let file = args.take_keyword("file").unwrap_or(Value::None);
+    let flush = args.take_keyword("flush").is_some_and(|v| v.to_bool());
+    args.reject_remaining("print", name)?;
+
+    let text = args
+        .positional
+        .iter()
+        .map(|v| v.str(&mut int.limits.borrow_mut()))
+        .collect::<Result<Vec<String>, String>>()
+        .map_err(|msg| error(msg, name))?;
+    let text = format!("{}{end}", text.join(&sep));
+    match file {
+        Value::None => (),
+        Value::File(f) => {
+            int.limits.borrow_mut().output(text.len(), name)?;
+            let mut f = f.borrow_mut();
+            f.write(&text).map_err(|msg| error(msg, name))?;
+            if flush {
+                f.flush().map_err(|msg| error(msg, name))?;
+            }
+            return Ok(Value::None);
+        }
+        v => {
+            return Err(error(
+                format!(
+                    "AttributeError: '{}' object has no attribute 'write'",
+                    v.type_name()
+                ),
+                name,
+            ))
+        }
+    }
+    int.limits.borrow_mut().output(text.len(), name)?;
+    let mut streams = int.streams.borrow_mut();
+    write!(streams.stdout, "{text}").map_err(|e| os_error(e, name))?;
+    if flush {
+        streams.stdout.flush().map_err(|e| os_error(e, name))?;
+    }
+    Ok(Value::None)
+}
+
+// input(prompt='') reads a line from stdin, without the newline at the end
+fn input(int: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("input", name)?;
+    check_arity("input", name, &args, 0, 1)?;
+    let prompt = match args.first() {
+        Some(p) => p
+            .str(&mut int.limits.borrow_mut())
+            .map_err(|msg| error(msg, name))?,
+        None => String::new(),
+    };
+    int.limits.borrow_mut().output(prompt.len(), name)?;
+    let mut streams = int.streams.borrow_mut();
+    write!(streams.stdout, "{prompt}").map_err(|e| os_error(e, name))?;
+    // the prompt has to be visible before waiting for the input
+    streams.stdout.flush().map_err(|e| os_error(e, name))?;
+
+    let mut line = String::new();
+    match streams.stdin.read_line(&mut line) {
+        Ok(0) => Err(error("EOFError: EOF when reading a line".to_owned(), name)),
+        Ok(_) => {
+            let line = line.strip_suffix('\n').unwrap_or(&line);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            Ok(Value::String(line.to_owned()))
+        }
+        Err(e) => Err(os_error(e, name)),
+    }
+}
+
+// open(file, mode='r'), see File::open for the supported modes
+fn open(_: &mut Interpreter, mut args: Args, name: &Name) -> Result<Value, PyError> {
+    let mode = args.take_keyword("mode");
+    let mut args = args.positional_only("open", name)?;
+    args.extend(mode);
+    check_arity("open", name, &args, 1, 2)?;
+    let Value::String(path) = &args[0] else {
+        return Err(error(
+            format!(
+                "TypeError: expected str, bytes or os.PathLike object, not {}",
+                args[0].type_name()
+            ),
+            name,
+        ));
+    };
+    let mode = match args.get(1) {
+        None => "r",
+        Some(Value::String(m)) => m,
+        Some(v) => {
+            return Err(error(
+                format!(
+                    "TypeError: open() argument 'mode' must be str, not {}",
+                    v.type_name()
+                ),
+                name,
+            ))
+        }
+    };
+    let file = File::open(path, mode).map_err(|msg| error(msg, name))?;
+    Ok(Value::File(Rc::new(RefCell::new(file))))
+}
+
+fn os_error(e: io::Error, name: &Name) -> PyError {
+    error(format!("OSError: {e}"), name)
+}
+
+// keyword argument that has to be a string, None means the default
+fn str_keyword(args: &mut Args, key: &str, default: &str, name: &Name) -> Result<String, PyError> {
+    match args.take_keyword(key) {
+        None | Some(Value::None) => Ok(default.to_owned()),
+        Some(Value::String(s)) => Ok(s),
+        Some(v) => Err(error(
+            format!(
+                "TypeError: {key} must be None or a string, not {}",
+                v.type_name()
+            ),
+            name,
+        )),
+    }
+}
+
+fn ord(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("ord", name)?;
+    check_arity("ord", name, &args, 1, 1)?;
+    match &args[0] {
+        Value::String(s) => {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(Value::Int(c as i128)),
+                _ => Err(error(
+                    format!(
+                        "TypeError: ord() expected a character, but string of length {} found",
+                        s.chars().count()
+                    ),
+                    name,
+                )),
+            }
+        }
+        v => Err(error(
+            format!(
+                "TypeError: ord() expected string of length 1, but {} found",
+                v.type_name()
+            ),
+            name,
+        )),
+    }
+}
+
+fn chr(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("chr", name)?;
+    check_arity("chr", name, &args, 1, 1)?;
+    match &args[0] {
+        Value::Int(n) => match u32::try_from(*n).ok().and_then(char::from_u32) {
+            Some(c) => Ok(Value::String(c.to_string())),
+            None => Err(error(
+                "ValueError: chr() arg not in range(0x110000)".to_owned(),
+                name,
+            )),
+        },
+        v => Err(error(
+            format!(
+                "TypeError: '{}' object cannot be interpreted as an integer",
+                v.type_name()
+            ),
+            name,
+        )),
+    }
+}
+
+// str(object='') converts any value into the string print() would show
+fn str(int: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("str", name)?;
+    check_arity("str", name, &args, 0, 1)?;
+    match args.first() {
+        Some(v) => {
+            let s = v.str(&mut int.limits.borrow_mut());
+            Ok(Value::String(s.map_err(|msg| error(msg, name))?))
+        }
+        None => Ok(Value::String(String::new())),
+    }
+}
+
+fn repr(int: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("repr", name)?;
+    check_arity("repr", name, &args, 1, 1)?;
+    let s = args[0].repr(&mut int.limits.borrow_mut());
+    Ok(Value::String(s.map_err(|msg| error(msg, name))?))
+}
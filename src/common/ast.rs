@@ -1,30 +1,76 @@
+use std::rc::Rc;
+
 #[derive(Debug, Clone)]
 pub enum Stmt {
     Expr(Expr),
-    Print(Expr),
     AssignVar(Name, Expr),
     AssignList(Name, Expr, Expr),
-    If(Expr, Vec<Stmt>, Option<Vec<Stmt>>),
-    While(Expr, Vec<Stmt>),
-    FunDecl(Name, Vec<Name>, Vec<Stmt>),
+    // the location of if, the condition, the body and the else body
+    If(Location, Expr, Vec<Stmt>, Option<Vec<Stmt>>),
+    // the location of while, the condition and the body
+    While(Location, Expr, Vec<Stmt>),
+    // with a as b: the location of with, the context manager and the name its __enter__() result is bound to
+    // several managers are nested with statements
+    With(Location, Expr, Option<Name>, Vec<Stmt>),
+    // the location of def, the name, the parameters and the body, which is shared with the functions made from it
+    FunDecl(Location, Name, Vec<Name>, Rc<Vec<Stmt>>),
     Return(Location, Option<Expr>),
+    // import a as b, c: the location of import and the modules with the names they are bound to if renamed
+    Import(Location, Vec<(Name, Option<Name>)>),
+    // from a import b as c, d: the location of from, the module and the imported names
+    FromImport(Location, Name, Vec<(Name, Option<Name>)>),
 }
 
+// the variants that can't get their span from their parts have it as their last field
 #[derive(Clone)]
 pub enum Expr {
     Unary(UnOp, Box<Expr>),
     Binary(Box<Expr>, BiOp, Box<Expr>),
-    Grouping(Box<Expr>),
-    Literal(Lit),
+    // chained comparison like a < b <= c, each operand is compared to the one before
+    Compare(Box<Expr>, Vec<(BiOp, Expr)>),
+    Grouping(Box<Expr>, Span),
+    Literal(Lit, Span),
     Variable(Name),
-    Call(Name, Vec<Expr>),
-    ListAccess(Name, Box<Expr>),
+    // positional and keyword arguments
+    Call(Name, Vec<Expr>, Vec<(Name, Expr)>, Span),
+    // the location of the [
+    ListAccess(Box<Expr>, Box<Expr>, Location, Span),
+    MethodCall(Box<Expr>, Name, Vec<Expr>, Vec<(Name, Expr)>, Span),
+    // object.name without a call, e.g. math.pi
+    Attribute(Box<Expr>, Name),
+    FString(Vec<FStringPart>, Span),
+    // start:stop:step, only allowed as the index of a ListAccess
+    Slice(
+        Option<Box<Expr>>,
+        Option<Box<Expr>>,
+        Option<Box<Expr>>,
+        Span,
+    ),
 }
 
-#[derive(Debug, Clone)]
+// the part of the source something was parsed from, as byte offsets, the end is exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    // from the start of this span to the end of the other one
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+        }
+    }
+}
+
+// the span is the one of the token, locations made by the compiler only have a line and column
+#[derive(Debug, Clone, Default)]
 pub struct Location {
     pub line: u64,
     pub column: u64,
+    pub span: Span,
 }
 
 #[derive(Clone)]
@@ -32,6 +78,7 @@ pub struct Name {
     pub name: String,
     pub line: u64,
     pub column: u64,
+    pub span: Span,
 }
 
 #[derive(Clone)]
@@ -39,22 +86,25 @@ pub struct UnOp {
     pub ty: UnOpType,
     pub line: u64,
     pub column: u64,
+    pub span: Span,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Copy)]
 pub enum UnOpType {
     Minus,
     Not,
 }
 
+// the span covers both words of not in and is not
 #[derive(Clone)]
 pub struct BiOp {
     pub ty: BiOpType,
     pub line: u64,
     pub column: u64,
+    pub span: Span,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Copy)]
 pub enum BiOpType {
     Plus,
     Minus,
@@ -66,10 +116,21 @@ pub enum BiOpType {
     GreaterEqual,
     Less,
     LessEqual,
+    In,
+    NotIn,
+    Is,
+    IsNot,
     And,
     Or,
 }
 
+#[derive(Clone)]
+pub enum FStringPart {
+    Literal(String),
+    // expression, conversion (!s, !r, !a), format spec and location of the expression
+    Field(Box<Expr>, Option<char>, Vec<FStringPart>, Location),
+}
+
 #[derive(Clone)]
 pub enum Lit {
     Int(u64),
@@ -81,6 +142,89 @@ pub enum Lit {
     None,
 }
 
+// where a statement is reported, e.g. when it is stopped by a quota or by the linter
+// None for the rare statements without names or operators, like a lone literal
+pub fn stmt_location(stmt: &Stmt) -> Option<Location> {
+    let name = |n: &Name| Location {
+        line: n.line,
+        column: n.column,
+        span: n.span,
+    };
+    match stmt {
+        Stmt::Expr(e) => expr_location(e),
+        Stmt::AssignVar(n, _) | Stmt::AssignList(n, ..) | Stmt::FunDecl(_, n, ..) => Some(name(n)),
+        Stmt::FromImport(_, n, _) => Some(name(n)),
+        Stmt::Import(_, names) => names.first().map(|(n, _)| name(n)),
+        Stmt::If(l, ..) | Stmt::While(l, ..) | Stmt::With(l, ..) | Stmt::Return(l, _) => {
+            Some(l.clone())
+        }
+    }
+}
+
+pub fn expr_location(expr: &Expr) -> Option<Location> {
+    let loc = |line, column, span| Some(Location { line, column, span });
+    match expr {
+        Expr::Unary(op, _) => loc(op.line, op.column, op.span),
+        Expr::Binary(_, op, _) => loc(op.line, op.column, op.span),
+        Expr::Compare(_, rest) => rest
+            .first()
+            .and_then(|(op, _)| loc(op.line, op.column, op.span)),
+        Expr::Variable(n)
+        | Expr::Call(n, ..)
+        | Expr::MethodCall(_, n, ..)
+        | Expr::Attribute(_, n) => loc(n.line, n.column, n.span),
+        Expr::ListAccess(_, _, l, _) => Some(l.clone()),
+        Expr::FString(parts, _) => parts.iter().find_map(|p| match p {
+            FStringPart::Field(_, _, _, l) => Some(l.clone()),
+            FStringPart::Literal(_) => None,
+        }),
+        Expr::Grouping(e, _) => expr_location(e),
+        Expr::Literal(..) | Expr::Slice(..) => None,
+    }
+}
+
+impl Stmt {
+    // from the first token of the statement to the last one of its body, without the newline
+    // the nested with statements of with a, b: all have the span of the whole statement
+    pub fn span(&self) -> Span {
+        let block =
+            |start: Span, stmts: &[Stmt]| stmts.last().map_or(start, |s| start.to(s.span()));
+        let names = |start: Span, names: &[(Name, Option<Name>)]| {
+            names.last().map_or(start, |(n, alias)| {
+                start.to(alias.as_ref().unwrap_or(n).span)
+            })
+        };
+        match self {
+            Stmt::Expr(e) => e.span(),
+            Stmt::AssignVar(n, e) | Stmt::AssignList(n, _, e) => n.span.to(e.span()),
+            Stmt::If(l, _, t, e) => block(l.span, e.as_ref().unwrap_or(t)),
+            Stmt::While(l, _, b) | Stmt::With(l, _, _, b) => block(l.span, b),
+            Stmt::FunDecl(l, _, _, b) => block(l.span, b),
+            Stmt::Return(l, e) => e.as_ref().map_or(l.span, |e| l.span.to(e.span())),
+            Stmt::Import(l, imported) | Stmt::FromImport(l, _, imported) => names(l.span, imported),
+        }
+    }
+}
+
+impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Unary(op, e) => op.span.to(e.span()),
+            Expr::Binary(e1, _, e2) => e1.span().to(e2.span()),
+            Expr::Compare(e, rest) => rest.last().map_or(e.span(), |(_, r)| e.span().to(r.span())),
+            Expr::Variable(n) => n.span,
+            Expr::Attribute(o, n) => o.span().to(n.span),
+            Expr::Grouping(_, s)
+            | Expr::Literal(_, s)
+            | Expr::Call(.., s)
+            | Expr::ListAccess(.., s)
+            | Expr::MethodCall(.., s)
+            | Expr::FString(_, s)
+            | Expr::Slice(.., s) => *s,
+        }
+    }
+}
+
 ////////////////////////////////////////////////////
 // debug trait implementations for nicer printing //
 ////////////////////////////////////////////////////
@@ -92,11 +236,50 @@ impl fmt::Debug for Expr {
         match self {
             Expr::Unary(op, ex) => write!(f, "({op:?} {ex:?})"),
             Expr::Binary(ex1, op, ex2) => write!(f, "({op:?} {ex1:?} {ex2:?})"),
-            Expr::Grouping(ex) => write!(f, "(group {ex:?})"),
-            Expr::Literal(l) => write!(f, "{l:?}"),
+            Expr::Compare(ex, rest) => {
+                write!(f, "(compare {ex:?}")?;
+                for (op, ex) in rest {
+                    write!(f, " {op:?} {ex:?}")?;
+                }
+                write!(f, ")")
+            }
+            Expr::Grouping(ex, _) => write!(f, "(group {ex:?})"),
+            Expr::Literal(l, _) => write!(f, "{l:?}"),
             Expr::Variable(n) => write!(f, "{n:?}"),
-            Expr::Call(n, p) => write!(f, "{n:?}({p:?})"),
-            Expr::ListAccess(n, i) => write!(f, "{n:?}[{i:?}]"),
+            Expr::Call(n, p, k, _) => write!(f, "{n:?}({p:?}, {k:?})"),
+            Expr::ListAccess(ex, i, ..) => write!(f, "{ex:?}[{i:?}]"),
+            Expr::MethodCall(ex, n, a, k, _) => write!(f, "{ex:?}.{n:?}({a:?}, {k:?})"),
+            Expr::Attribute(ex, n) => write!(f, "{ex:?}.{n:?}"),
+            Expr::FString(p, _) => write!(f, "f{p:?}"),
+            Expr::Slice(a, b, c, _) => {
+                for (i, bound) in [a, b, c].into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ":")?;
+                    }
+                    if let Some(ex) = bound {
+                        write!(f, "{ex:?}")?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Debug for FStringPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FStringPart::Literal(s) => write!(f, "\"{s}\""),
+            FStringPart::Field(ex, c, s, _) => {
+                write!(f, "{{{ex:?}")?;
+                if let Some(c) = c {
+                    write!(f, "!{c}")?;
+                }
+                if !s.is_empty() {
+                    write!(f, ":{s:?}")?;
+                }
+                write!(f, "}}")
+            }
         }
     }
 }
@@ -129,6 +312,10 @@ impl fmt::Debug for BiOp {
             BiOpType::GreaterEqual => write!(f, ">="),
             BiOpType::Less => write!(f, "<"),
             BiOpType::LessEqual => write!(f, "<="),
+            BiOpType::In => write!(f, "in"),
+            BiOpType::NotIn => write!(f, "not in"),
+            BiOpType::Is => write!(f, "is"),
+            BiOpType::IsNot => write!(f, "is not"),
             BiOpType::And => write!(f, "and"),
             BiOpType::Or => write!(f, "or"),
         }
@@ -1,3 +1,5 @@
+use super::ast::Span;
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum TokenType {
     // single-character
@@ -11,7 +13,7 @@ pub enum TokenType {
     LeftBracket,
     RightBracket,
     Comma,
-    // Point,
+    Point,
     // Percent,
     EndOfLine,
 
@@ -32,31 +34,66 @@ pub enum TokenType {
     Not,
     And,
     Or,
+    In,
+    Is,
     If,
     Else,
     While,
+    With,
     Def,
     Return,
-    Print,
+    Import,
+    From,
+    As,
     None,
 
     // literals
     Identifier(String),
     String(String),
+    FString(Vec<FStringSegment>),
     Int(u64), // this is only ever positive, bc negative numbers are built by the parser
     Float(f64),
+    // the text after the #, never part of the tokens passed to the parser
+    Comment(String),
 
     Indent,
     Dedent,
     EndOfFile,
 }
 
-#[derive(Debug, Clone)]
+// part of an f-string token, fields already contain the scanned tokens of their expression
+#[derive(PartialEq, Debug, Clone)]
+pub enum FStringSegment {
+    Literal(String),
+    Field {
+        tokens: Vec<Token>,
+        conversion: Option<char>,
+        spec: Vec<FStringSegment>,
+    },
+}
+
+// what the scanner skips, kept for the formatter
+#[derive(PartialEq, Debug, Clone)]
+pub enum Trivia {
+    // own_line is the depth of indentation of a comment on a line without code, None for one after code
+    Comment {
+        text: String,
+        line: u64,
+        own_line: Option<usize>,
+    },
+    BlankLine(u64),
+    // the start of a string literal written with three quotes, as a byte offset like the spans
+    TripleQuoted(usize),
+}
+
+#[derive(PartialEq, Debug, Clone)]
 pub struct Token {
     pub token_type: TokenType,
     pub value: String,
     pub line: u64,
     pub column: u64,
+    // where value is in the source, for f-string fields the tokens of their expressions have their own
+    pub span: Span,
 }
 
 impl Token {
@@ -68,222 +105,315 @@ impl Token {
                 value: "+".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Minus => Self {
                 token_type,
                 value: "-".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Asterisk => Self {
                 token_type,
                 value: "*".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Slash => Self {
                 token_type,
                 value: "/".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Colon => Self {
                 token_type,
                 value: ":".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::LeftParen => Self {
                 token_type,
                 value: "(".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::RightParen => Self {
                 token_type,
                 value: ")".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::LeftBracket => Self {
                 token_type,
                 value: "[".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::RightBracket => Self {
                 token_type,
                 value: "]".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Comma => Self {
                 token_type,
                 value: ",".to_owned(),
                 line,
                 column,
+                span: Span::default(),
+            },
+            TokenType::Point => Self {
+                token_type,
+                value: ".".to_owned(),
+                line,
+                column,
+                span: Span::default(),
             },
             TokenType::NotEqual => Self {
                 token_type,
                 value: "!=".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Equal => Self {
                 token_type,
                 value: "=".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::DoubleEqual => Self {
                 token_type,
                 value: "==".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Greater => Self {
                 token_type,
                 value: ">".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::GreaterEqual => Self {
                 token_type,
                 value: ">=".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Less => Self {
                 token_type,
                 value: "<".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::LessEqual => Self {
                 token_type,
                 value: "<=".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::True => Self {
                 token_type,
                 value: "True".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::False => Self {
                 token_type,
                 value: "False".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Not => Self {
                 token_type,
                 value: "not".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::And => Self {
                 token_type,
                 value: "and".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Or => Self {
                 token_type,
                 value: "or".to_owned(),
                 line,
                 column,
+                span: Span::default(),
+            },
+            TokenType::In => Self {
+                token_type,
+                value: "in".to_owned(),
+                line,
+                column,
+                span: Span::default(),
+            },
+            TokenType::Is => Self {
+                token_type,
+                value: "is".to_owned(),
+                line,
+                column,
+                span: Span::default(),
             },
             TokenType::If => Self {
                 token_type,
                 value: "if".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Else => Self {
                 token_type,
                 value: "else".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::While => Self {
                 token_type,
                 value: "while".to_owned(),
                 line,
                 column,
+                span: Span::default(),
+            },
+            TokenType::With => Self {
+                token_type,
+                value: "with".to_owned(),
+                line,
+                column,
+                span: Span::default(),
             },
             TokenType::Def => Self {
                 token_type,
                 value: "def".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Return => Self {
                 token_type,
                 value: "return".to_owned(),
                 line,
                 column,
+                span: Span::default(),
+            },
+            TokenType::Import => Self {
+                token_type,
+                value: "import".to_owned(),
+                line,
+                column,
+                span: Span::default(),
+            },
+            TokenType::From => Self {
+                token_type,
+                value: "from".to_owned(),
+                line,
+                column,
+                span: Span::default(),
             },
-            TokenType::Print => Self {
+            TokenType::As => Self {
                 token_type,
-                value: "print".to_owned(),
+                value: "as".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::None => Self {
                 token_type,
                 value: "None".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Identifier(ref n) => Self {
                 value: n.to_owned(),
                 token_type,
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::String(ref s) => Self {
                 value: format!("\"{s}\""),
                 token_type,
                 line,
                 column,
+                span: Span::default(),
+            },
+            TokenType::FString(_) => Self {
+                token_type,
+                value: "f\"...\"".to_owned(),
+                line,
+                column,
+                span: Span::default(),
             },
             TokenType::Int(x) => Self {
                 token_type,
                 value: x.to_string(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Float(x) => Self {
                 token_type,
                 value: x.to_string(),
                 line,
                 column,
+                span: Span::default(),
+            },
+            TokenType::Comment(ref c) => Self {
+                value: format!("#{c}"),
+                token_type,
+                line,
+                column,
+                span: Span::default(),
             },
             TokenType::EndOfLine => Self {
                 token_type,
                 value: "\n".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Indent => Self {
                 token_type,
                 value: "".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::Dedent => Self {
                 token_type,
                 value: "".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
             TokenType::EndOfFile => Self {
                 token_type,
                 value: "".to_owned(),
                 line,
                 column,
+                span: Span::default(),
             },
         }
     }
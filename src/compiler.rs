@@ -0,0 +1,523 @@
+// compiles the AST into bytecode for the stack machine in interpreter/vm.rs
+// the order in which things are evaluated (and fail) is the same as in the tree-walker
+
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    common::ast::*,
+    interpreter::Value,
+    resolver::{self, Access, FunctionScope},
+};
+
+#[derive(Debug, Clone)]
+pub enum Instr {
+    // pushes constants[i]
+    Const(usize),
+    // pushes or pops the global variable names[i]
+    LoadGlobal(usize),
+    StoreGlobal(usize),
+    // pushes or pops the variable in a slot of the running function
+    LoadLocal(usize),
+    StoreLocal(usize),
+    // pushes or pops the variable in a cell of the running function
+    LoadCell(usize),
+    StoreCell(usize),
+    Pop,
+    // makes a list from the top n values
+    BuildList(usize),
+    // joins the top n strings, for f-strings
+    BuildString(usize),
+    // pops the format spec and the value of an f-string field and pushes the formatted string
+    FormatValue(Option<char>),
+    Unary(UnOpType),
+    Binary(BiOpType),
+    // a comparison in the middle of a chain, jumps to the end with the result if it's false
+    // otherwise the right operand stays as the left operand of the next comparison
+    Compare(BiOpType, usize),
+    Jump(usize),
+    // pops the condition
+    JumpIfFalse(usize),
+    // calls the function below the arguments, names[name] is the called name and keywords[keywords] the keyword names
+    // the keyword arguments are on top of the positional ones
+    Call {
+        name: usize,
+        args: usize,
+        keywords: usize,
+    },
+    // pushes the function names[i] of a module (None for other objects) above the object,
+    // so a missing function fails before the arguments are evaluated
+    LoadMethod(usize),
+    // like Call, but with the object and the result of LoadMethod below the arguments
+    CallMethod {
+        name: usize,
+        args: usize,
+        keywords: usize,
+    },
+    GetAttr(usize),
+    // pops the index and the list or string
+    GetItem,
+    // checks the slice bound that was just evaluated, the step (the last bound) can't be zero
+    SliceBound {
+        step: bool,
+    },
+    // pops the three bounds and the list or string
+    GetSlice,
+    // checks that the value of the variable names[i] is a list, to assign to its elements
+    ExpectList(usize),
+    // checks the index below the list and turns it into a position in the list
+    CheckIndex,
+    // pops the value, the list and the index
+    SetItem,
+    // pops the value, the list and the three bounds
+    SetSlice,
+    // pushes a function made from functions[i], with the cells it uses of the running function
+    MakeFunction(usize),
+    // pushes the module names[i]
+    Import(usize),
+    // pushes names[i] of the module on top of the stack, the module stays
+    ImportFrom(usize),
+    // pops the context manager and pushes the result of its __enter__
+    EnterWith,
+    // calls __exit__ of the context manager of the innermost with statement
+    ExitWith,
+    // leaves all with statements and the function with the value on top of the stack
+    Return,
+    // fails with the message in constants[i]
+    Raise(usize),
+}
+
+#[derive(Debug, Default)]
+pub struct Code {
+    pub instructions: Vec<Instr>,
+    // where each instruction comes from, for errors
+    pub locations: Vec<Location>,
+    pub constants: Vec<Value>,
+    pub names: Vec<String>,
+    // the keyword names of every call with keyword arguments
+    pub keywords: Vec<Vec<String>>,
+    pub functions: Vec<FunctionCode>,
+    // for functions, see FunctionScope: where the arguments go and the names of the slots and cells
+    pub parameters: Vec<Access>,
+    pub slots: Vec<String>,
+    pub cells: Vec<String>,
+    pub own_cells: usize,
+}
+
+// a def statement, the function is created when the statement is run
+#[derive(Debug)]
+pub struct FunctionCode {
+    pub name: Name,
+    pub parameters: Vec<Name>,
+    pub code: Rc<Code>,
+    // the cells of the defining function that become the free variables of this one
+    pub free: Vec<usize>,
+}
+
+// compiles the top level of a module
+pub fn compile(stmts: &[Stmt]) -> Code {
+    let mut compiler = Compiler {
+        code: Code::default(),
+        location: Location::default(),
+        scope: None,
+        constant_indices: HashMap::new(),
+        name_indices: HashMap::new(),
+    };
+    compiler.block(stmts);
+    compiler.code
+}
+
+fn compile_function(parameters: &[Name], body: &[Stmt], scope: FunctionScope) -> Code {
+    let code = Code {
+        parameters: parameters.iter().map(|p| scope.access(&p.name)).collect(),
+        slots: scope.slots.clone(),
+        cells: scope.cells.clone(),
+        own_cells: scope.own_cells,
+        ..Code::default()
+    };
+    let mut compiler = Compiler {
+        code,
+        location: Location::default(),
+        scope: Some(scope),
+        constant_indices: HashMap::new(),
+        name_indices: HashMap::new(),
+    };
+    compiler.block(body);
+    compiler.code
+}
+
+struct Compiler {
+    code: Code,
+    // location of the next emitted instruction
+    location: Location,
+    // None at the top level of a module
+    scope: Option<FunctionScope>,
+    // where the constants and names already are in the code
+    constant_indices: HashMap<ConstantKey, usize>,
+    name_indices: HashMap<String, usize>,
+}
+
+// what makes two constants the same, floats by their bits, so 0.0 and -0.0 stay apart
+#[derive(PartialEq, Eq, Hash)]
+enum ConstantKey {
+    Int(i128),
+    Float(u64),
+    String(String),
+    Bool(bool),
+    None,
+}
+
+impl ConstantKey {
+    fn new(val: &Value) -> Self {
+        match val {
+            Value::Int(n) => ConstantKey::Int(*n),
+            Value::Float(x) => ConstantKey::Float(x.to_bits()),
+            Value::String(s) => ConstantKey::String(s.clone()),
+            Value::Bool(b) => ConstantKey::Bool(*b),
+            Value::None => ConstantKey::None,
+            v => unreachable!("{} values aren't constants", v.type_name()),
+        }
+    }
+}
+
+impl Compiler {
+    ////////////////
+    // statements //
+    ////////////////
+
+    fn block(&mut self, stmts: &[Stmt]) {
+        for st in stmts {
+            self.statement(st);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expr(e) => {
+                self.expression(e);
+                self.emit(Instr::Pop);
+            }
+            Stmt::AssignVar(n, e) => {
+                self.expression(e);
+                self.store(n);
+            }
+            Stmt::AssignList(n, i, e) => {
+                let list = self.name(&n.name);
+                if let Expr::Slice(start, stop, step, _) = i {
+                    self.slice([start, stop, step], n.line, n.column);
+                    self.load(n);
+                    self.emit(Instr::ExpectList(list));
+                    self.expression(e);
+                    self.at(n.line, n.column).emit(Instr::SetSlice);
+                } else {
+                    self.expression(i);
+                    self.load(n);
+                    self.emit(Instr::ExpectList(list));
+                    self.emit(Instr::CheckIndex);
+                    self.expression(e);
+                    self.at(n.line, n.column).emit(Instr::SetItem);
+                }
+            }
+            Stmt::If(_, c, t, e) => {
+                self.expression(c);
+                let to_else = self.emit(Instr::JumpIfFalse(0));
+                self.block(t);
+                if let Some(e) = e {
+                    let to_end = self.emit(Instr::Jump(0));
+                    self.patch(to_else);
+                    self.block(e);
+                    self.patch(to_end);
+                } else {
+                    self.patch(to_else);
+                }
+            }
+            Stmt::While(l, c, b) => {
+                let start = self.code.instructions.len();
+                self.at(l.line, l.column).expression(c);
+                let to_end = self.emit(Instr::JumpIfFalse(0));
+                self.block(b);
+                self.at(l.line, l.column).emit(Instr::Jump(start));
+                self.patch(to_end);
+            }
+            Stmt::With(l, e, a, b) => {
+                self.expression(e);
+                self.at(l.line, l.column).emit(Instr::EnterWith);
+                match a {
+                    Some(alias) => self.store(alias),
+                    None => {
+                        self.emit(Instr::Pop);
+                    }
+                }
+                self.block(b);
+                self.at(l.line, l.column).emit(Instr::ExitWith);
+            }
+            Stmt::FunDecl(_, n, p, b) => {
+                let scope = FunctionScope::new(p, b, self.scope.as_ref());
+                // the resolver makes every free variable of a nested function a cell of the enclosing one
+                let free = scope
+                    .free()
+                    .iter()
+                    .map(|name| match self.access(name) {
+                        Access::Cell(i) => i,
+                        a => unreachable!("free variable {name} is {a:?} outside"),
+                    })
+                    .collect();
+                self.code.functions.push(FunctionCode {
+                    name: n.clone(),
+                    parameters: p.clone(),
+                    code: Rc::new(compile_function(p, b, scope)),
+                    free,
+                });
+                let f = self.code.functions.len() - 1;
+                self.at(n.line, n.column).emit(Instr::MakeFunction(f));
+                self.store(n);
+            }
+            Stmt::Return(l, e) => {
+                match e {
+                    Some(e) => self.expression(e),
+                    None => self.constant(Value::None),
+                }
+                self.at(l.line, l.column).emit(Instr::Return);
+            }
+            Stmt::Import(_, names) => {
+                for (name, alias) in names {
+                    let module = self.name(&name.name);
+                    self.at(name.line, name.column).emit(Instr::Import(module));
+                    self.store(alias.as_ref().unwrap_or(name));
+                }
+            }
+            Stmt::FromImport(_, module, names) => {
+                let m = self.name(&module.name);
+                self.at(module.line, module.column).emit(Instr::Import(m));
+                for (name, alias) in names {
+                    let imported = self.name(&name.name);
+                    self.at(name.line, name.column)
+                        .emit(Instr::ImportFrom(imported));
+                    self.store(alias.as_ref().unwrap_or(name));
+                }
+                self.emit(Instr::Pop);
+            }
+        }
+    }
+
+    /////////////////
+    // expressions //
+    /////////////////
+
+    fn expression(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Unary(op, e) => {
+                self.expression(e);
+                self.at(op.line, op.column).emit(Instr::Unary(op.ty));
+            }
+            Expr::Binary(e1, op, e2) => {
+                self.expression(e1);
+                self.expression(e2);
+                self.at(op.line, op.column).emit(Instr::Binary(op.ty));
+            }
+            Expr::Compare(first, rest) => {
+                self.expression(first);
+                let mut to_end = Vec::new();
+                for (i, (op, e)) in rest.iter().enumerate() {
+                    self.expression(e);
+                    self.at(op.line, op.column);
+                    if i + 1 < rest.len() {
+                        to_end.push(self.emit(Instr::Compare(op.ty, 0)));
+                    } else {
+                        self.emit(Instr::Binary(op.ty));
+                    }
+                }
+                for jump in to_end {
+                    self.patch(jump);
+                }
+            }
+            Expr::Grouping(e, _) => self.expression(e),
+            Expr::Literal(l, _) => self.literal(l),
+            Expr::Variable(n) => self.load(n),
+            Expr::Call(n, a, k, _) => {
+                let name = self.name(&n.name);
+                self.load(n);
+                let keywords = self.arguments(a, k);
+                self.at(n.line, n.column).emit(Instr::Call {
+                    name,
+                    args: a.len(),
+                    keywords,
+                });
+            }
+            Expr::ListAccess(t, i, l, _) => {
+                self.expression(t);
+                if let Expr::Slice(start, stop, step, _) = &**i {
+                    self.slice([start, stop, step], l.line, l.column);
+                    self.at(l.line, l.column).emit(Instr::GetSlice);
+                } else {
+                    self.expression(i);
+                    self.at(l.line, l.column).emit(Instr::GetItem);
+                }
+            }
+            Expr::MethodCall(o, n, a, k, _) => {
+                self.expression(o);
+                let name = self.name(&n.name);
+                self.at(n.line, n.column).emit(Instr::LoadMethod(name));
+                let keywords = self.arguments(a, k);
+                self.at(n.line, n.column).emit(Instr::CallMethod {
+                    name,
+                    args: a.len(),
+                    keywords,
+                });
+            }
+            Expr::Attribute(o, n) => {
+                self.expression(o);
+                let name = self.name(&n.name);
+                self.at(n.line, n.column).emit(Instr::GetAttr(name));
+            }
+            Expr::FString(p, _) => self.fstring(p),
+            // the parser only allows slices inside of subscripts, which compile them themselves
+            Expr::Slice(..) => unreachable!("slice outside of subscript"),
+        }
+    }
+
+    fn literal(&mut self, lit: &Lit) {
+        match lit {
+            Lit::Int(n) => self.constant(Value::Int((*n).into())),
+            Lit::Float(n) => self.constant(Value::Float(*n)),
+            Lit::String(s) => self.constant(Value::String(s.clone())),
+            Lit::True => self.constant(Value::Bool(true)),
+            Lit::False => self.constant(Value::Bool(false)),
+            Lit::None => self.constant(Value::None),
+            Lit::List(elems) => {
+                for e in elems {
+                    self.expression(e);
+                }
+                self.emit(Instr::BuildList(elems.len()));
+            }
+        }
+    }
+
+    fn fstring(&mut self, parts: &[FStringPart]) {
+        for part in parts {
+            match part {
+                FStringPart::Literal(s) => self.constant(Value::String(s.clone())),
+                FStringPart::Field(ex, conv, spec, loc) => {
+                    self.expression(ex);
+                    // the spec can contain fields itself
+                    self.fstring(spec);
+                    self.at(loc.line, loc.column)
+                        .emit(Instr::FormatValue(*conv));
+                }
+            }
+        }
+        self.emit(Instr::BuildString(parts.len()));
+    }
+
+    // evaluates the positional and keyword arguments of a call, returns the index of the keyword names
+    fn arguments(&mut self, args: &[Expr], keywords: &[(Name, Expr)]) -> usize {
+        for a in args {
+            self.expression(a);
+        }
+        let mut names: Vec<String> = Vec::new();
+        for (key, ex) in keywords {
+            // fails when it is reached, like in the tree-walker
+            if names.contains(&key.name) {
+                let msg = format!("SyntaxError: keyword argument repeated: {}", key.name);
+                let msg = self.add_constant(Value::String(msg));
+                self.at(key.line, key.column).emit(Instr::Raise(msg));
+            }
+            self.expression(ex);
+            names.push(key.name.clone());
+        }
+        self.code.keywords.push(names);
+        self.code.keywords.len() - 1
+    }
+
+    // pushes the three bounds, None for the missing ones
+    fn slice(&mut self, bounds: [&Option<Box<Expr>>; 3], line: u64, column: u64) {
+        for (i, bound) in bounds.into_iter().enumerate() {
+            match bound {
+                Some(ex) => {
+                    self.expression(ex);
+                    self.at(line, column)
+                        .emit(Instr::SliceBound { step: i == 2 });
+                }
+                None => self.constant(Value::None),
+            }
+        }
+    }
+
+    /////////////
+    // helpers //
+    /////////////
+
+    // sets the location of the following instructions
+    fn at(&mut self, line: u64, column: u64) -> &mut Self {
+        self.location = Location {
+            line,
+            column,
+            span: Span::default(),
+        };
+        self
+    }
+
+    // returns the position of the instruction, for patching jumps
+    fn emit(&mut self, instr: Instr) -> usize {
+        self.code.instructions.push(instr);
+        self.code.locations.push(self.location.clone());
+        self.code.instructions.len() - 1
+    }
+
+    // makes the jump at the given position go to the next instruction
+    fn patch(&mut self, jump: usize) {
+        let target = self.code.instructions.len();
+        match &mut self.code.instructions[jump] {
+            Instr::Jump(t) | Instr::JumpIfFalse(t) | Instr::Compare(_, t) => *t = target,
+            i => unreachable!("{i:?} is not a jump"),
+        }
+    }
+
+    fn constant(&mut self, val: Value) {
+        let c = self.add_constant(val);
+        self.emit(Instr::Const(c));
+    }
+
+    // equal constants of the same type are only stored once
+    fn add_constant(&mut self, val: Value) -> usize {
+        let constants = &mut self.code.constants;
+        *self
+            .constant_indices
+            .entry(ConstantKey::new(&val))
+            .or_insert_with(|| {
+                constants.push(val);
+                constants.len() - 1
+            })
+    }
+
+    fn access(&self, name: &str) -> Access {
+        resolver::access(self.scope.as_ref(), name)
+    }
+
+    fn load(&mut self, var: &Name) {
+        let instr = match self.access(&var.name) {
+            Access::Local(i) => Instr::LoadLocal(i),
+            Access::Cell(i) => Instr::LoadCell(i),
+            Access::Global => Instr::LoadGlobal(self.name(&var.name)),
+        };
+        self.at(var.line, var.column).emit(instr);
+    }
+
+    fn store(&mut self, var: &Name) {
+        let instr = match self.access(&var.name) {
+            Access::Local(i) => Instr::StoreLocal(i),
+            Access::Cell(i) => Instr::StoreCell(i),
+            Access::Global => Instr::StoreGlobal(self.name(&var.name)),
+        };
+        self.at(var.line, var.column).emit(instr);
+    }
+
+    fn name(&mut self, name: &str) -> usize {
+        if let Some(&i) = self.name_indices.get(name) {
+            return i;
+        }
+        self.code.names.push(name.to_owned());
+        self.name_indices
+            .insert(name.to_owned(), self.code.names.len() - 1);
+        self.code.names.len() - 1
+    }
+}
@@ -0,0 +1,324 @@
+// the concrete syntax tree: the syntax tree with every token and the text between the tokens,
+// so writing out its leaves gives back the source exactly, e.g. for tools that only change parts of a script
+
+use std::fmt;
+
+use crate::common::{ast::*, token::*};
+
+pub enum Cst {
+    Node {
+        kind: NodeKind,
+        span: Span,
+        children: Vec<Cst>,
+    },
+    // the text of f-strings around their fields, with quotes, braces and conversions, is a token of type FString
+    Token {
+        token_type: TokenType,
+        span: Span,
+        text: String,
+    },
+    // spaces, newlines that don't end a statement, and comments (one leaf each)
+    Trivia {
+        span: Span,
+        text: String,
+    },
+}
+
+// the statements and expressions of ast.rs, the nested with statements of with a, b: are one node
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeKind {
+    Module,
+    Block,
+    ExprStmt,
+    AssignVar,
+    AssignList,
+    If,
+    While,
+    With,
+    FunDecl,
+    Return,
+    Import,
+    FromImport,
+    Unary,
+    Binary,
+    Compare,
+    Grouping,
+    Literal,
+    Variable,
+    Call,
+    ListAccess,
+    MethodCall,
+    Attribute,
+    FString,
+    Slice,
+}
+
+impl Cst {
+    pub fn span(&self) -> Span {
+        match self {
+            Cst::Node { span, .. } | Cst::Token { span, .. } | Cst::Trivia { span, .. } => *span,
+        }
+    }
+
+    // one line per node and leaf, indented by depth
+    pub fn tree(&self) -> String {
+        let mut out = String::new();
+        self.write_tree(&mut out, 0);
+        out
+    }
+
+    fn write_tree(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let Span { start, end } = self.span();
+        match self {
+            Cst::Node { kind, children, .. } => {
+                out.push_str(&format!("{indent}{kind:?} {start}..{end}\n"));
+                for c in children {
+                    c.write_tree(out, depth + 1);
+                }
+            }
+            Cst::Token {
+                token_type, text, ..
+            } => out.push_str(&format!(
+                "{indent}{} {start}..{end} {text:?}\n",
+                token_name(token_type)
+            )),
+            Cst::Trivia { text, .. } => {
+                out.push_str(&format!("{indent}Trivia {start}..{end} {text:?}\n"))
+            }
+        }
+    }
+}
+
+// the source the tree was built from
+impl fmt::Display for Cst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cst::Node { children, .. } => children.iter().try_for_each(|c| write!(f, "{c}")),
+            Cst::Token { text, .. } | Cst::Trivia { text, .. } => write!(f, "{text}"),
+        }
+    }
+}
+
+// the type without its value, e.g. Identifier
+fn token_name(ty: &TokenType) -> String {
+    match ty {
+        TokenType::Identifier(_) => "Identifier".to_owned(),
+        TokenType::String(_) => "String".to_owned(),
+        TokenType::FString(_) => "FString".to_owned(),
+        TokenType::Int(_) => "Int".to_owned(),
+        TokenType::Float(_) => "Float".to_owned(),
+        TokenType::Comment(_) => "Comment".to_owned(),
+        other => format!("{other:?}"),
+    }
+}
+
+// the tokens are the ones the statements were parsed from, with their spans
+pub fn build(code: &str, tokens: &[Token], stmts: &[Stmt]) -> Cst {
+    let mut leaves = Vec::new();
+    flatten(tokens, &mut leaves);
+    let builder = Builder { code, leaves };
+    let module = Span {
+        start: 0,
+        end: code.len(),
+    };
+    let children = stmts.iter().map(|s| builder.stmt(s)).collect();
+    builder.node(NodeKind::Module, module, children)
+}
+
+// the tokens with text in the order they appear, f-strings are split into their text and the tokens of their fields
+fn flatten(tokens: &[Token], leaves: &mut Vec<(TokenType, Span)>) {
+    for t in tokens {
+        if t.span.start == t.span.end {
+            continue;
+        }
+        let TokenType::FString(segments) = &t.token_type else {
+            leaves.push((t.token_type.clone(), t.span));
+            continue;
+        };
+        let mut fields = Vec::new();
+        field_tokens(segments, &mut fields);
+        let text = |start, end| (TokenType::FString(Vec::new()), Span { start, end });
+        let mut pos = t.span.start;
+        for field in fields {
+            let mut inner = Vec::new();
+            flatten(field, &mut inner);
+            let (Some((_, first)), Some((_, last))) = (inner.first(), inner.last()) else {
+                continue;
+            };
+            // there is always at least a brace between two fields
+            leaves.push(text(pos, first.start));
+            pos = last.end;
+            leaves.extend(inner);
+        }
+        leaves.push(text(pos, t.span.end));
+    }
+}
+
+// the tokens of every field in order, the fields of format specs come after the expression they belong to
+fn field_tokens<'a>(segments: &'a [FStringSegment], fields: &mut Vec<&'a [Token]>) {
+    for seg in segments {
+        if let FStringSegment::Field { tokens, spec, .. } = seg {
+            fields.push(tokens);
+            field_tokens(spec, fields);
+        }
+    }
+}
+
+struct Builder<'a> {
+    code: &'a str,
+    // sorted by their start
+    leaves: Vec<(TokenType, Span)>,
+}
+
+impl Builder<'_> {
+    // the children are the nodes inside of the span, the tokens and trivia around them are added here
+    fn node(&self, kind: NodeKind, span: Span, mut children: Vec<Cst>) -> Cst {
+        children.sort_by_key(|c| c.span().start);
+        let mut all = Vec::new();
+        let mut pos = span.start;
+        for c in children {
+            self.gap(pos, c.span().start, &mut all);
+            pos = c.span().end;
+            all.push(c);
+        }
+        self.gap(pos, span.end, &mut all);
+        Cst::Node {
+            kind,
+            span,
+            children: all,
+        }
+    }
+
+    // the tokens between start and end, and the trivia between them
+    fn gap(&self, mut start: usize, end: usize, out: &mut Vec<Cst>) {
+        let first = self.leaves.partition_point(|(_, s)| s.start < start);
+        for (ty, span) in &self.leaves[first..] {
+            if span.end > end {
+                break;
+            }
+            self.trivia(start, span.start, out);
+            out.push(Cst::Token {
+                token_type: ty.clone(),
+                span: *span,
+                text: self.code[span.start..span.end].to_owned(),
+            });
+            start = span.end;
+        }
+        self.trivia(start, end, out);
+    }
+
+    // splits the text into comments and the whitespace between them
+    fn trivia(&self, mut start: usize, end: usize, out: &mut Vec<Cst>) {
+        while start < end {
+            let rest = &self.code[start..end];
+            let len = if rest.starts_with('#') {
+                rest.find('\n')
+            } else {
+                rest.find('#')
+            }
+            .unwrap_or(rest.len());
+            out.push(Cst::Trivia {
+                span: Span {
+                    start,
+                    end: start + len,
+                },
+                text: rest[..len].to_owned(),
+            });
+            start += len;
+        }
+    }
+
+    fn block(&self, stmts: &[Stmt]) -> Option<Cst> {
+        let span = stmts.first()?.span().to(stmts.last()?.span());
+        let children = stmts.iter().map(|s| self.stmt(s)).collect();
+        Some(self.node(NodeKind::Block, span, children))
+    }
+
+    fn stmt(&self, stmt: &Stmt) -> Cst {
+        let (kind, children) = match stmt {
+            Stmt::Expr(e) => (NodeKind::ExprStmt, vec![self.expr(e)]),
+            Stmt::AssignVar(_, e) => (NodeKind::AssignVar, vec![self.expr(e)]),
+            Stmt::AssignList(_, i, e) => (NodeKind::AssignList, vec![self.expr(i), self.expr(e)]),
+            Stmt::If(_, c, t, e) => {
+                let mut children = vec![self.expr(c)];
+                children.extend(self.block(t));
+                children.extend(e.as_ref().and_then(|e| self.block(e)));
+                (NodeKind::If, children)
+            }
+            Stmt::While(_, c, b) => {
+                let mut children = vec![self.expr(c)];
+                children.extend(self.block(b));
+                (NodeKind::While, children)
+            }
+            Stmt::With(loc, e, _, b) => {
+                let mut children = vec![self.expr(e)];
+                let mut body = b;
+                // the items after the first one are parsed into with statements at the same location
+                while let [Stmt::With(l, e, _, b)] = body.as_slice() {
+                    if l.span != loc.span {
+                        break;
+                    }
+                    children.push(self.expr(e));
+                    body = b;
+                }
+                children.extend(self.block(body));
+                (NodeKind::With, children)
+            }
+            Stmt::FunDecl(_, _, _, b) => (NodeKind::FunDecl, self.block(b).into_iter().collect()),
+            Stmt::Return(_, e) => (NodeKind::Return, e.iter().map(|e| self.expr(e)).collect()),
+            Stmt::Import(..) => (NodeKind::Import, Vec::new()),
+            Stmt::FromImport(..) => (NodeKind::FromImport, Vec::new()),
+        };
+        self.node(kind, stmt.span(), children)
+    }
+
+    fn expr(&self, expr: &Expr) -> Cst {
+        let exprs = |es: &[Expr]| es.iter().map(|e| self.expr(e)).collect::<Vec<_>>();
+        let call = |args: &[Expr], keywords: &[(Name, Expr)]| {
+            let mut children = exprs(args);
+            children.extend(keywords.iter().map(|(_, e)| self.expr(e)));
+            children
+        };
+        let (kind, children) = match expr {
+            Expr::Unary(_, e) => (NodeKind::Unary, vec![self.expr(e)]),
+            Expr::Binary(e1, _, e2) => (NodeKind::Binary, vec![self.expr(e1), self.expr(e2)]),
+            Expr::Compare(e, rest) => {
+                let mut children = vec![self.expr(e)];
+                children.extend(rest.iter().map(|(_, e)| self.expr(e)));
+                (NodeKind::Compare, children)
+            }
+            Expr::Grouping(e, _) => (NodeKind::Grouping, vec![self.expr(e)]),
+            Expr::Literal(Lit::List(elems), _) => (NodeKind::Literal, exprs(elems)),
+            Expr::Literal(..) => (NodeKind::Literal, Vec::new()),
+            Expr::Variable(_) => (NodeKind::Variable, Vec::new()),
+            Expr::Call(_, a, k, _) => (NodeKind::Call, call(a, k)),
+            Expr::ListAccess(t, i, ..) => (NodeKind::ListAccess, vec![self.expr(t), self.expr(i)]),
+            Expr::MethodCall(o, _, a, k, _) => {
+                let mut children = vec![self.expr(o)];
+                children.extend(call(a, k));
+                (NodeKind::MethodCall, children)
+            }
+            Expr::Attribute(o, _) => (NodeKind::Attribute, vec![self.expr(o)]),
+            Expr::FString(parts, _) => {
+                let mut children = Vec::new();
+                self.fields(parts, &mut children);
+                (NodeKind::FString, children)
+            }
+            Expr::Slice(start, stop, step, _) => {
+                let bounds = [start, stop, step].into_iter().flatten();
+                (NodeKind::Slice, bounds.map(|e| self.expr(e)).collect())
+            }
+        };
+        self.node(kind, expr.span(), children)
+    }
+
+    fn fields(&self, parts: &[FStringPart], out: &mut Vec<Cst>) {
+        for part in parts {
+            if let FStringPart::Field(e, _, spec, _) = part {
+                out.push(self.expr(e));
+                self.fields(spec, out);
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+// file objects returned by open(), text only
+// files opened for reading are read completely at once, written ones are buffered until closed
+
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::{self, BufWriter, Write},
+};
+
+pub struct File {
+    pub name: String,
+    pub mode: String,
+    handle: Handle,
+}
+
+enum Handle {
+    // the whole content and the byte offset of the next char to read
+    Read { content: String, pos: usize },
+    Write(BufWriter<fs::File>),
+    Closed,
+}
+
+impl fmt::Debug for File {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<file '{}'>", self.name)
+    }
+}
+
+impl File {
+    // open(path, mode) with the modes r, w, a and x, optionally followed by t
+    // errors are the messages of the python exceptions
+    pub fn open(path: &str, mode: &str) -> Result<Self, String> {
+        let kind = mode.strip_suffix('t').unwrap_or(mode);
+        let handle = match kind {
+            "r" => {
+                let content = fs::read_to_string(path).map_err(|e| os_error(e, path))?;
+                // like python's universal newlines
+                Handle::Read {
+                    content: content.replace("\r\n", "\n"),
+                    pos: 0,
+                }
+            }
+            "w" | "a" | "x" => {
+                let mut options = OpenOptions::new();
+                match kind {
+                    "w" => options.write(true).create(true).truncate(true),
+                    "a" => options.append(true).create(true),
+                    _ => options.write(true).create_new(true),
+                };
+                let file = options.open(path).map_err(|e| os_error(e, path))?;
+                Handle::Write(BufWriter::new(file))
+            }
+            _ => return Err(format!("ValueError: invalid mode: '{mode}'")),
+        };
+        Ok(File {
+            name: path.to_owned(),
+            mode: mode.to_owned(),
+            handle,
+        })
+    }
+
+    // the unread rest of the content, or why the file can't be read
+    fn unread(&mut self) -> Result<(&str, &mut usize), String> {
+        match &mut self.handle {
+            Handle::Read { content, pos } => Ok((&content[*pos..], pos)),
+            Handle::Write(_) => Err("io.UnsupportedOperation: not readable".to_owned()),
+            Handle::Closed => Err(closed()),
+        }
+    }
+
+    // at most size chars, everything that is left without a size
+    pub fn read(&mut self, size: Option<usize>) -> Result<String, String> {
+        let (rest, pos) = self.unread()?;
+        let len = match size {
+            Some(n) => rest.char_indices().nth(n).map_or(rest.len(), |(i, _)| i),
+            None => rest.len(),
+        };
+        let res = rest[..len].to_owned();
+        *pos += len;
+        Ok(res)
+    }
+
+    // the next line including its newline, empty at the end of the file
+    pub fn readline(&mut self) -> Result<String, String> {
+        let (rest, pos) = self.unread()?;
+        let len = rest.find('\n').map_or(rest.len(), |i| i + 1);
+        let res = rest[..len].to_owned();
+        *pos += len;
+        Ok(res)
+    }
+
+    pub fn readlines(&mut self) -> Result<Vec<String>, String> {
+        let mut lines = Vec::new();
+        loop {
+            let line = self.readline()?;
+            if line.is_empty() {
+                return Ok(lines);
+            }
+            lines.push(line);
+        }
+    }
+
+    pub fn write(&mut self, text: &str) -> Result<(), String> {
+        match &mut self.handle {
+            Handle::Write(w) => w
+                .write_all(text.as_bytes())
+                .map_err(|e| os_error(e, &self.name)),
+            Handle::Read { .. } => Err("io.UnsupportedOperation: not writable".to_owned()),
+            Handle::Closed => Err(closed()),
+        }
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        match &mut self.handle {
+            Handle::Write(w) => w.flush().map_err(|e| os_error(e, &self.name)),
+            Handle::Read { .. } => Ok(()),
+            Handle::Closed => Err(closed()),
+        }
+    }
+
+    // closing twice is allowed, like in python
+    pub fn close(&mut self) -> Result<(), String> {
+        if let Handle::Closed = self.handle {
+            return Ok(());
+        }
+        let res = self.flush();
+        self.handle = Handle::Closed;
+        res
+    }
+
+    pub fn closed(&self) -> bool {
+        matches!(self.handle, Handle::Closed)
+    }
+}
+
+fn closed() -> String {
+    "ValueError: I/O operation on closed file.".to_owned()
+}
+
+// e.g. FileNotFoundError: [Errno 2] No such file or directory: 'a.txt'
+fn os_error(e: io::Error, path: &str) -> String {
+    let kind = match e.kind() {
+        io::ErrorKind::NotFound => "FileNotFoundError",
+        io::ErrorKind::PermissionDenied => "PermissionError",
+        io::ErrorKind::AlreadyExists => "FileExistsError",
+        io::ErrorKind::IsADirectory => "IsADirectoryError",
+        _ => "OSError",
+    };
+    // rust appends the error number to the description
+    let desc = e.to_string();
+    let desc = desc.split(" (os error").next().unwrap_or(&desc).to_owned();
+    match e.raw_os_error() {
+        Some(n) => format!("{kind}: [Errno {n}] {desc}: '{path}'"),
+        None => format!("{kind}: {desc}: '{path}'"),
+    }
+}
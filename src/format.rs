@@ -0,0 +1,434 @@
+// implementation of the format spec mini-language used by f-string fields, e.g. f"{x:>10.2f}"
+// see https://docs.python.org/3/library/string.html#format-specification-mini-language
+
+use std::iter;
+
+use crate::{
+    interpreter::Value,
+    limits::{self, Limits},
+};
+
+// [[fill]align][sign][#][0][width][grouping][.precision][type]
+struct FormatSpec {
+    fill: char,
+    align: Option<char>,
+    sign: char,
+    alternate: bool,
+    width: usize,
+    grouping: Option<char>,
+    precision: Option<usize>,
+    ty: Option<char>,
+}
+
+impl FormatSpec {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let chars: Vec<char> = spec.chars().collect();
+        let mut fs = FormatSpec {
+            fill: ' ',
+            align: None,
+            sign: '-',
+            alternate: false,
+            width: 0,
+            grouping: None,
+            precision: None,
+            ty: None,
+        };
+        let is_align = |c: &char| matches!(c, '<' | '>' | '=' | '^');
+        let mut i = 0;
+        let mut explicit_fill = false;
+        if chars.get(1).is_some_and(is_align) {
+            fs.fill = chars[0];
+            fs.align = Some(chars[1]);
+            explicit_fill = true;
+            i = 2;
+        } else if chars.first().is_some_and(is_align) {
+            fs.align = Some(chars[0]);
+            i = 1;
+        }
+        if let Some(c @ ('+' | '-' | ' ')) = chars.get(i) {
+            fs.sign = *c;
+            i += 1;
+        }
+        if chars.get(i) == Some(&'#') {
+            fs.alternate = true;
+            i += 1;
+        }
+        // zero padding is the same as fill 0 with = alignment
+        if chars.get(i) == Some(&'0') {
+            if !explicit_fill {
+                fs.fill = '0';
+            }
+            fs.align.get_or_insert('=');
+            i += 1;
+        }
+        while let Some(d) = chars.get(i).and_then(|c| c.to_digit(10)) {
+            fs.width = push_digit(fs.width, d)?;
+            i += 1;
+        }
+        if let Some(c @ (',' | '_')) = chars.get(i) {
+            fs.grouping = Some(*c);
+            i += 1;
+        }
+        if chars.get(i) == Some(&'.') {
+            i += 1;
+            let mut precision = None;
+            while let Some(d) = chars.get(i).and_then(|c| c.to_digit(10)) {
+                precision = Some(push_digit(precision.unwrap_or(0), d)?);
+                i += 1;
+            }
+            if precision.is_none() {
+                return Err("ValueError: Format specifier missing precision".to_owned());
+            }
+            fs.precision = precision;
+        }
+        if let Some(c) = chars.get(i) {
+            fs.ty = Some(*c);
+            i += 1;
+        }
+        if i < chars.len() {
+            return Err("ValueError: Invalid format specifier".to_owned());
+        }
+        Ok(fs)
+    }
+
+    // pads the formatted value up to the width, prefix is the sign (and 0x etc.) which = alignment puts before the padding
+    // the width can be anything the script asks for, so the memory is checked before it is taken
+    fn pad(&self, prefix: &str, body: &str, default_align: char) -> Result<String, String> {
+        let n = self
+            .width
+            .saturating_sub(prefix.chars().count() + body.chars().count());
+        let (left, middle, right) = match self.align.unwrap_or(default_align) {
+            '<' => (0, 0, n),
+            '^' => (n / 2, 0, n - n / 2),
+            '=' => (0, n, 0),
+            _ => (n, 0, 0),
+        };
+        let bytes = n
+            .saturating_mul(self.fill.len_utf8())
+            .saturating_add(prefix.len() + body.len());
+        let mut s = limits::string(bytes)?;
+        s.extend(iter::repeat_n(self.fill, left));
+        s.push_str(prefix);
+        s.extend(iter::repeat_n(self.fill, middle));
+        s.push_str(body);
+        s.extend(iter::repeat_n(self.fill, right));
+        Ok(s)
+    }
+
+    fn sign_str(&self, negative: bool) -> &'static str {
+        match (negative, self.sign) {
+            (true, _) => "-",
+            (false, '+') => "+",
+            (false, ' ') => " ",
+            _ => "",
+        }
+    }
+}
+
+// the number with the digit appended, an error if it gets too big
+fn push_digit(n: usize, d: u32) -> Result<usize, String> {
+    n.checked_mul(10)
+        .and_then(|n| n.checked_add(d as usize))
+        .ok_or_else(|| "ValueError: Too many decimal digits in format string".to_owned())
+}
+
+// applies the !s, !r or !a after the expression of an f-string field
+pub fn convert(val: Value, conversion: Option<char>, limits: &mut Limits) -> Result<Value, String> {
+    Ok(match conversion {
+        Some('r') => Value::String(val.repr(limits)?),
+        Some('a') => Value::String(val.ascii(limits)?),
+        Some(_) => Value::String(val.str(limits)?),
+        None => val,
+    })
+}
+
+// formats a value according to the spec after the : of an f-string field
+pub fn format_value(val: &Value, spec: &str, limits: &mut Limits) -> Result<String, String> {
+    if spec.is_empty() {
+        return val.str(limits);
+    }
+    let fs = FormatSpec::parse(spec)?;
+    match val {
+        Value::String(s) => format_string(s, &fs),
+        Value::Int(n) => format_int(*n, &fs),
+        Value::Bool(b) => format_int(*b as i128, &fs),
+        Value::Float(x) => format_float(*x, &fs, "float"),
+        _ => Err(format!(
+            "TypeError: unsupported format string passed to {}.__format__",
+            val.type_name()
+        )),
+    }
+}
+
+fn format_string(s: &str, fs: &FormatSpec) -> Result<String, String> {
+    match fs.ty {
+        None | Some('s') => (),
+        Some(c) => {
+            return Err(format!(
+                "ValueError: Unknown format code '{c}' for object of type 'str'"
+            ))
+        }
+    }
+    if fs.sign != '-' {
+        return Err("ValueError: Sign not allowed in string format specifier".to_owned());
+    }
+    if fs.alternate {
+        return Err(
+            "ValueError: Alternate form (#) not allowed in string format specifier".to_owned(),
+        );
+    }
+    if fs.align == Some('=') {
+        return Err("ValueError: '=' alignment not allowed in string format specifier".to_owned());
+    }
+    let text: String = match fs.precision {
+        Some(p) => s.chars().take(p).collect(),
+        None => s.to_owned(),
+    };
+    fs.pad("", &text, '<')
+}
+
+fn format_int(n: i128, fs: &FormatSpec) -> Result<String, String> {
+    let ty = fs.ty.unwrap_or('d');
+    if let 'e' | 'E' | 'f' | 'F' | 'g' | 'G' | '%' = ty {
+        return format_float(n as f64, fs, "int");
+    }
+    if fs.precision.is_some() {
+        return Err("ValueError: Precision not allowed in integer format specifier".to_owned());
+    }
+    let abs = n.unsigned_abs();
+    let (prefix, digits) = match ty {
+        'd' | 'n' => ("", abs.to_string()),
+        'x' => ("0x", format!("{abs:x}")),
+        'X' => ("0X", format!("{abs:X}")),
+        'o' => ("0o", format!("{abs:o}")),
+        'b' => ("0b", format!("{abs:b}")),
+        'c' => {
+            let c = u32::try_from(n)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or("OverflowError: %c arg not in range(0x110000)".to_owned())?;
+            return fs.pad("", &c.to_string(), '<');
+        }
+        c => {
+            return Err(format!(
+                "ValueError: Unknown format code '{c}' for object of type 'int'"
+            ))
+        }
+    };
+    let digits = match fs.grouping {
+        Some(',') if !prefix.is_empty() => {
+            return Err(format!("ValueError: Cannot specify ',' with '{ty}'."));
+        }
+        Some(sep) => group_digits(&digits, sep, if prefix.is_empty() { 3 } else { 4 }),
+        None => digits,
+    };
+    let prefix = if fs.alternate { prefix } else { "" };
+    let sign = fs.sign_str(n < 0);
+    fs.pad(&format!("{sign}{prefix}"), &digits, '>')
+}
+
+fn format_float(x: f64, fs: &FormatSpec, type_name: &str) -> Result<String, String> {
+    let abs = x.abs();
+    let body = match fs.ty {
+        _ if x.is_nan() => "nan".to_owned(),
+        _ if x.is_infinite() => "inf".to_owned(),
+        Some('f' | 'F') => {
+            let s = fixed(abs, fs.precision.unwrap_or(6))?;
+            if fs.alternate && !s.contains('.') {
+                s + "."
+            } else {
+                s
+            }
+        }
+        Some('e' | 'E') => scientific(abs, fs.precision.unwrap_or(6))?,
+        Some('g' | 'G') => general(abs, fs.precision.unwrap_or(6), fs.alternate)?,
+        Some('%') => fixed(abs * 100.0, fs.precision.unwrap_or(6))? + "%",
+        // without a type it's like g, but fixed point always has a decimal
+        None | Some('n') => match fs.precision {
+            Some(p) => {
+                let s = general(abs, p, fs.alternate)?;
+                if s.contains(['.', 'e']) {
+                    s
+                } else {
+                    s + ".0"
+                }
+            }
+            None => float_repr(abs),
+        },
+        Some(c) => {
+            return Err(format!(
+                "ValueError: Unknown format code '{c}' for object of type '{type_name}'"
+            ))
+        }
+    };
+    let body = match fs.ty {
+        Some('F' | 'E' | 'G') => body.to_uppercase(),
+        _ => body,
+    };
+    let body = match fs.grouping {
+        Some(sep) => {
+            // only the integer part gets separators
+            let int_len = body.find(['.', 'e', 'E', '%']).unwrap_or(body.len());
+            let (int_part, rest) = body.split_at(int_len);
+            format!("{}{rest}", group_digits(int_part, sep, 3))
+        }
+        None => body,
+    };
+    let sign = fs.sign_str(x.is_sign_negative() && !x.is_nan());
+    fs.pad(sign, &body, '>')
+}
+
+// puts a separator between every group of digits counted from the right
+fn group_digits(digits: &str, sep: char, every: usize) -> String {
+    let chars: Vec<char> = digits.chars().collect();
+    let mut res = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if i > 0 && (chars.len() - i).is_multiple_of(every) {
+            res.push(sep);
+        }
+        res.push(*c);
+    }
+    res
+}
+
+// splits rust's exponent formatting (e.g. 1.5e3) into mantissa and exponent
+fn split_exponent(s: &str) -> (&str, i32) {
+    let (mantissa, exp) = s
+        .split_once('e')
+        .expect("rust's exponent formatting always contains an e");
+    (
+        mantissa,
+        exp.parse()
+            .expect("rust's exponent formatting always has an integer exponent"),
+    )
+}
+
+// python always puts a sign and at least two digits in the exponent, e.g. 1.5e+03
+fn python_exponent(mantissa: &str, exp: i32) -> String {
+    let sign = if exp < 0 { '-' } else { '+' };
+    format!("{mantissa}e{sign}{:02}", exp.abs())
+}
+
+// rust can't format with a precision above u16::MAX, but an f64 has at most 1074 decimals after the point
+// and 767 significant digits, so everything after this many digits is a zero that can be added by hand
+const EXACT_DIGITS: usize = 1100;
+
+// the exact digits with the zeros after them, which can be as many as the script asks for
+fn with_zeros(digits: &str, zeros: usize, rest: &str) -> Result<String, String> {
+    let mut s = limits::string(digits.len().saturating_add(zeros) + rest.len())?;
+    s.push_str(digits);
+    s.extend(iter::repeat_n('0', zeros));
+    s.push_str(rest);
+    Ok(s)
+}
+
+// {:.*} for any precision
+fn fixed(x: f64, precision: usize) -> Result<String, String> {
+    let shown = precision.min(EXACT_DIGITS);
+    with_zeros(&format!("{:.*}", shown, x), precision - shown, "")
+}
+
+// {:.*e} for any precision
+fn exponential(x: f64, precision: usize) -> Result<String, String> {
+    let shown = precision.min(EXACT_DIGITS);
+    let s = format!("{:.*e}", shown, x);
+    let (mantissa, exp) = split_exponent(&s);
+    with_zeros(mantissa, precision - shown, &format!("e{exp}"))
+}
+
+fn scientific(x: f64, precision: usize) -> Result<String, String> {
+    let s = exponential(x, precision)?;
+    let (mantissa, exp) = split_exponent(&s);
+    Ok(python_exponent(mantissa, exp))
+}
+
+// g format: fixed point or scientific depending on the exponent, without trailing zeros
+fn general(x: f64, precision: usize, alternate: bool) -> Result<String, String> {
+    let precision = precision.max(1);
+    let s = exponential(x, precision - 1)?;
+    let (mantissa, exp) = split_exponent(&s);
+    let strip = |s: String| {
+        if !alternate && s.contains('.') {
+            s.trim_end_matches('0').trim_end_matches('.').to_owned()
+        } else {
+            s
+        }
+    };
+    // compared as usize, precision can be bigger than any i32
+    if -4 <= exp && (exp < 0 || (exp as usize) < precision) {
+        Ok(strip(fixed(
+            x,
+            (precision - 1).saturating_add_signed(-exp as isize),
+        )?))
+    } else {
+        Ok(python_exponent(&strip(mantissa.to_owned()), exp))
+    }
+}
+
+// shortest representation that round-trips, like python's repr(float)
+pub fn float_repr(x: f64) -> String {
+    if x.is_nan() {
+        return "nan".to_owned();
+    }
+    if x.is_infinite() {
+        return if x > 0.0 { "inf" } else { "-inf" }.to_owned();
+    }
+    // rust's {:e} already gives the shortest digits, only the notation has to be adjusted
+    let s = format!("{:e}", x.abs());
+    let (mantissa, exp) = split_exponent(&s);
+    let sign = if x.is_sign_negative() { "-" } else { "" };
+    if !(-4..16).contains(&exp) {
+        return format!("{sign}{}", python_exponent(mantissa, exp));
+    }
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let body = if exp < 0 {
+        format!("0.{}{digits}", "0".repeat((-exp - 1) as usize))
+    } else if digits.len() as i32 > exp + 1 {
+        let (int_part, frac) = digits.split_at(exp as usize + 1);
+        format!("{int_part}.{frac}")
+    } else {
+        format!(
+            "{digits}{}.0",
+            "0".repeat((exp + 1) as usize - digits.len())
+        )
+    };
+    format!("{sign}{body}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(val: &Value, spec: &str) -> Result<String, String> {
+        format_value(val, spec, &mut Limits::unlimited())
+    }
+
+    #[test]
+    fn precision_above_what_rust_can_format() {
+        let x = Value::Float(1.5);
+        let f = format(&x, ".99999f").unwrap();
+        assert_eq!(f.len(), 2 + 99999);
+        assert!(f.starts_with("1.5000") && f.ends_with("000"));
+        let e = format(&x, ".99999e").unwrap();
+        assert!(e.starts_with("1.5000") && e.ends_with("0e+00"));
+        assert_eq!(e.len(), 2 + 99999 + 4);
+        assert_eq!(format(&x, ".99999").unwrap(), "1.5");
+        assert_eq!(format(&x, "#.99999g").unwrap().len(), 1 + 99999);
+    }
+
+    #[test]
+    fn exact_digits_before_the_added_zeros() {
+        // 0.1 is 0.1000000000000000055511151231257827..., the digits are kept up to where they end
+        let f = format(&Value::Float(0.1), ".1200f").unwrap();
+        assert!(f.starts_with("0.1000000000000000055511151231257827"));
+        assert_eq!(f.len(), 2 + 1200);
+    }
+
+    #[test]
+    fn too_many_digits() {
+        let err = "ValueError: Too many decimal digits in format string";
+        let x = Value::Float(1.5);
+        assert_eq!(format(&x, "99999999999999999999999").unwrap_err(), err);
+        assert_eq!(format(&x, ".99999999999999999999999").unwrap_err(), err);
+    }
+}
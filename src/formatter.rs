@@ -0,0 +1,435 @@
+// the pretty printer of the fmt subcommand, prints the syntax tree back as code in one style:
+// 4 spaces of indentation, spaces around operators and after commas, no more blank lines in a row than 1
+// (2 between top-level statements), and lists and arguments that don't fit into a line are split over several
+// comments come from the scanner, they go before the next statement or at the end of the line they were on
+
+use std::collections::HashSet;
+
+use crate::common::{ast::*, token::Trivia};
+
+// longest line, unless a single part of it is too long already
+const WIDTH: usize = 88;
+const INDENT: &str = "    ";
+
+// the code is the source the statements were parsed from, for the lines of their spans
+pub fn format(code: &str, stmts: &[Stmt], trivia: Vec<Trivia>) -> String {
+    let mut f = Formatter {
+        line_starts: std::iter::once(0)
+            .chain(code.match_indices('\n').map(|(i, _)| i + 1))
+            .collect(),
+        lines: Vec::new(),
+        comments: Vec::new(),
+        blank: HashSet::new(),
+        triple_quoted: HashSet::new(),
+        block_start: true,
+        last_line: 0,
+    };
+    for t in trivia {
+        match t {
+            Trivia::Comment {
+                text,
+                line,
+                own_line,
+            } => f.comments.push(Comment {
+                text,
+                line,
+                own_line,
+            }),
+            Trivia::BlankLine(line) => {
+                f.blank.insert(line);
+            }
+            Trivia::TripleQuoted(start) => {
+                f.triple_quoted.insert(start);
+            }
+        }
+    }
+    // taken from the back
+    f.comments.reverse();
+    f.block(stmts, 0, None);
+
+    f.lines.iter().map(|l| format!("{l}\n")).collect()
+}
+
+struct Comment {
+    text: String,
+    line: u64,
+    own_line: Option<usize>,
+}
+
+struct Formatter {
+    // the byte offsets at which the lines of the source start
+    line_starts: Vec<usize>,
+    lines: Vec<String>,
+    // the comments that aren't printed yet, the next one last
+    comments: Vec<Comment>,
+    blank: HashSet<u64>,
+    // the starts of the string literals that are triple quoted in the source, they stay like that
+    triple_quoted: HashSet<usize>,
+    // no blank lines right after the start of the file or a block
+    block_start: bool,
+    // the last line of the source that was printed, as far as it is known
+    last_line: u64,
+}
+
+impl Formatter {
+    // end is the line of the statement after the block, to know which comments belong to it
+    fn block(&mut self, stmts: &[Stmt], depth: usize, end: Option<u64>) {
+        for (i, st) in stmts.iter().enumerate() {
+            let line = self.first_line(st);
+            self.comments_before(line, depth);
+            let next = stmts.get(i + 1).map(|s| self.first_line(s)).or(end);
+            self.statement(st, depth, line, next);
+        }
+        self.comments_after(depth, end);
+    }
+
+    fn statement(&mut self, stmt: &Stmt, depth: usize, line: u64, next: Option<u64>) {
+        match stmt {
+            Stmt::Expr(e) => self.code(depth, Some(line), self.split("", e, "", depth)),
+            Stmt::AssignVar(n, e) => {
+                let prefix = format!("{} = ", n.name);
+                self.code(depth, Some(line), self.split(&prefix, e, "", depth));
+            }
+            Stmt::AssignList(n, i, e) => {
+                let prefix = format!("{}[{}] = ", n.name, self.expr(i, '"'));
+                self.code(depth, Some(line), self.split(&prefix, e, "", depth));
+            }
+            Stmt::If(_, c, t, e) => {
+                self.code(depth, Some(line), self.split("if ", c, ":", depth));
+                let else_line = e
+                    .as_ref()
+                    .and_then(|e| e.first())
+                    .map(|s| self.first_line(s));
+                self.body(t, depth, else_line.or(next));
+                if let Some(e) = e {
+                    self.code(depth, None, vec![(0, "else:".to_owned())]);
+                    self.body(e, depth, next);
+                }
+            }
+            Stmt::While(_, c, b) => {
+                self.code(depth, Some(line), self.split("while ", c, ":", depth));
+                self.body(b, depth, next);
+            }
+            Stmt::With(..) => {
+                // with a, b: is parsed as nested with statements at the same location
+                let mut items = Vec::new();
+                let mut with = stmt;
+                let body = loop {
+                    let Stmt::With(l, e, alias, b) = with else {
+                        unreachable!("only with statements are looked at here");
+                    };
+                    items.push(match alias {
+                        Some(a) => format!("{} as {}", self.expr(e, '"'), a.name),
+                        None => self.expr(e, '"'),
+                    });
+                    match b.as_slice() {
+                        [inner @ Stmt::With(l2, ..)]
+                            if (l2.line, l2.column) == (l.line, l.column) =>
+                        {
+                            with = inner;
+                        }
+                        _ => break b,
+                    }
+                };
+                let header = format!("with {}:", items.join(", "));
+                self.code(depth, Some(line), vec![(0, header)]);
+                self.body(body, depth, next);
+            }
+            Stmt::FunDecl(_, n, params, b) => {
+                let params: Vec<&str> = params.iter().map(|p| p.name.as_str()).collect();
+                let header = format!("def {}({}):", n.name, params.join(", "));
+                self.code(depth, Some(line), vec![(0, header)]);
+                self.body(b, depth, next);
+            }
+            Stmt::Return(_, e) => {
+                let code = match e {
+                    Some(e) => self.split("return ", e, "", depth),
+                    None => vec![(0, "return".to_owned())],
+                };
+                self.code(depth, Some(line), code);
+            }
+            Stmt::Import(_, names) => {
+                let code = format!("import {}", import_names(names));
+                self.code(depth, Some(line), vec![(0, code)]);
+            }
+            Stmt::FromImport(_, module, names) => {
+                let code = format!("from {} import {}", module.name, import_names(names));
+                self.code(depth, Some(line), vec![(0, code)]);
+            }
+        }
+    }
+
+    // the line a statement starts on
+    fn first_line(&self, stmt: &Stmt) -> u64 {
+        self.line_starts
+            .partition_point(|&start| start <= stmt.span().start) as u64
+    }
+
+    fn body(&mut self, stmts: &[Stmt], depth: usize, end: Option<u64>) {
+        self.block_start = true;
+        self.block(stmts, depth + 1, end);
+    }
+
+    // adds the lines of a statement, indented relative to depth, with the blank lines before it in the source
+    fn code(&mut self, depth: usize, line: Option<u64>, code: Vec<(usize, String)>) {
+        if let Some(line) = line {
+            self.blank_lines(depth, line);
+            self.last_line = self.last_line.max(line);
+        }
+        for (indent, text) in code {
+            self.lines.push(INDENT.repeat(depth + indent) + &text);
+        }
+        self.block_start = false;
+    }
+
+    fn blank_lines(&mut self, depth: usize, line: u64) {
+        if self.block_start {
+            return;
+        }
+        let max = if depth == 0 { 2 } else { 1 };
+        let count = (1..line)
+            .rev()
+            .take_while(|l| self.blank.contains(l))
+            .take(max)
+            .count();
+        self.lines.extend((0..count).map(|_| String::new()));
+    }
+
+    // the comments above a statement, and the ones at the end of lines before it
+    fn comments_before(&mut self, line: u64, depth: usize) {
+        while let Some(c) = self.comments.pop_if(|c| c.line < line) {
+            self.comment(c, depth);
+        }
+    }
+
+    // the comments at the end of a block, up to the first one indented less than the block
+    fn comments_after(&mut self, depth: usize, end: Option<u64>) {
+        while let Some(c) = self
+            .comments
+            .pop_if(|c| end.is_none_or(|end| c.line < end) && c.own_line.is_none_or(|d| d >= depth))
+        {
+            self.comment(c, depth);
+        }
+    }
+
+    fn comment(&mut self, c: Comment, depth: usize) {
+        let text = c.text.trim_end();
+        // a space after the #, except for e.g. #! lines and empty comments
+        let text = if text.is_empty() || text.starts_with([' ', '!']) {
+            format!("#{text}")
+        } else {
+            format!("# {text}")
+        };
+        match (c.own_line, self.lines.last_mut()) {
+            (None, Some(last)) => {
+                last.push_str("  ");
+                last.push_str(&text);
+            }
+            _ => self.code(depth, Some(c.line), vec![(0, text)]),
+        }
+    }
+}
+
+fn import_names(names: &[(Name, Option<Name>)]) -> String {
+    let names: Vec<String> = names
+        .iter()
+        .map(|(n, alias)| match alias {
+            Some(a) => format!("{} as {}", n.name, a.name),
+            None => n.name.clone(),
+        })
+        .collect();
+    names.join(", ")
+}
+
+// positional arguments with an empty name, then keyword arguments with name=
+fn items<'a>(args: &'a [Expr], keywords: &'a [(Name, Expr)]) -> Vec<(String, &'a Expr)> {
+    let positional = args.iter().map(|e| (String::new(), e));
+    let keywords = keywords.iter().map(|(n, e)| (format!("{}=", n.name), e));
+    positional.chain(keywords).collect()
+}
+
+fn fits(depth: usize, line: &str) -> bool {
+    depth * INDENT.len() + line.chars().count() <= WIDTH
+}
+
+impl Formatter {
+    // the lines of prefix, expression and suffix with their indentation,
+    // the list or arguments of the expression are split over several lines if they don't fit into one
+    fn split(&self, prefix: &str, ex: &Expr, suffix: &str, depth: usize) -> Vec<(usize, String)> {
+        let flat = format!("{prefix}{}{suffix}", self.expr(ex, '"'));
+        let (open, items, close) = match ex {
+            Expr::Literal(Lit::List(elems), _) => ("[".to_owned(), items(elems, &[]), "]"),
+            Expr::Call(n, args, keywords, _) => {
+                (format!("{}(", n.name), items(args, keywords), ")")
+            }
+            Expr::MethodCall(o, n, args, keywords, _) => (
+                format!("{}.{}(", self.expr(o, '"'), n.name),
+                items(args, keywords),
+                ")",
+            ),
+            _ => return vec![(0, flat)],
+        };
+        if fits(depth, &flat) || items.is_empty() {
+            return vec![(0, flat)];
+        }
+
+        let mut lines = vec![(0, format!("{prefix}{open}"))];
+        let joined: Vec<String> = items
+            .iter()
+            .map(|(name, e)| format!("{name}{}", self.expr(e, '"')))
+            .collect();
+        let joined = joined.join(", ");
+        if fits(depth + 1, &joined) {
+            lines.push((1, joined));
+        } else {
+            // one item per line, with a comma after each like black does it
+            for (name, e) in items {
+                let item = self.split(&name, e, ",", depth + 1);
+                lines.extend(item.into_iter().map(|(indent, text)| (indent + 1, text)));
+            }
+        }
+        lines.push((0, format!("{close}{suffix}")));
+        lines
+    }
+
+    // an expression on one line, quote is the one used for strings
+    // (fields of f-strings can't contain the quote of the f-string)
+    fn expr(&self, ex: &Expr, quote: char) -> String {
+        match ex {
+            Expr::Unary(op, e) => match op.ty {
+                UnOpType::Minus => format!("-{}", self.expr(e, quote)),
+                UnOpType::Not => format!("not {}", self.expr(e, quote)),
+            },
+            Expr::Binary(e1, op, e2) => {
+                format!("{} {op:?} {}", self.expr(e1, quote), self.expr(e2, quote))
+            }
+            Expr::Compare(e, rest) => {
+                let mut code = self.expr(e, quote);
+                for (op, e) in rest {
+                    code += &format!(" {op:?} {}", self.expr(e, quote));
+                }
+                code
+            }
+            Expr::Grouping(e, _) => format!("({})", self.expr(e, quote)),
+            Expr::Literal(lit, span) => match lit {
+                Lit::Int(n) => n.to_string(),
+                // without an exponent, the scanner can't read those, but always with a point
+                Lit::Float(x) => {
+                    let x = x.to_string();
+                    if x.contains('.') {
+                        x
+                    } else {
+                        x + ".0"
+                    }
+                }
+                Lit::String(s) => self.string(s, span.start, quote),
+                Lit::List(elems) => format!("[{}]", self.args(elems, &[], quote)),
+                Lit::True => "True".to_owned(),
+                Lit::False => "False".to_owned(),
+                Lit::None => "None".to_owned(),
+            },
+            Expr::Variable(n) => n.name.clone(),
+            Expr::Call(n, a, k, _) => format!("{}({})", n.name, self.args(a, k, quote)),
+            Expr::ListAccess(e, i, ..) => {
+                format!("{}[{}]", self.expr(e, quote), self.expr(i, quote))
+            }
+            Expr::MethodCall(o, n, a, k, _) => {
+                format!(
+                    "{}.{}({})",
+                    self.expr(o, quote),
+                    n.name,
+                    self.args(a, k, quote)
+                )
+            }
+            Expr::Attribute(o, n) => format!("{}.{}", self.expr(o, quote), n.name),
+            Expr::FString(parts, _) => {
+                format!("f{quote}{}{quote}", self.fstring(parts, quote, false))
+            }
+            Expr::Slice(start, stop, step, _) => {
+                let bound = |e: &Option<Box<Expr>>| {
+                    e.as_ref().map_or(String::new(), |e| self.expr(e, quote))
+                };
+                match step {
+                    Some(_) => format!("{}:{}:{}", bound(start), bound(stop), bound(step)),
+                    None => format!("{}:{}", bound(start), bound(stop)),
+                }
+            }
+        }
+    }
+
+    fn args(&self, args: &[Expr], keywords: &[(Name, Expr)], quote: char) -> String {
+        let args: Vec<String> = items(args, keywords)
+            .into_iter()
+            .map(|(name, e)| format!("{name}{}", self.expr(e, quote)))
+            .collect();
+        args.join(", ")
+    }
+
+    // the parts between the quotes, in a format spec the literal text is taken as it is
+    fn fstring(&self, parts: &[FStringPart], quote: char, spec: bool) -> String {
+        let inner = if quote == '"' { '\'' } else { '"' };
+        let mut code = String::new();
+        for part in parts {
+            match part {
+                FStringPart::Literal(text) if spec => code += text,
+                FStringPart::Literal(text) => {
+                    code += &escape(text, quote).replace('{', "{{").replace('}', "}}")
+                }
+                FStringPart::Field(e, conversion, format_spec, _) => {
+                    code.push('{');
+                    code += &self.expr(e, inner);
+                    if let Some(c) = conversion {
+                        code.push('!');
+                        code.push(*c);
+                    }
+                    if !format_spec.is_empty() {
+                        code.push(':');
+                        code += &self.fstring(format_spec, quote, true);
+                    }
+                    code.push('}');
+                }
+            }
+        }
+        code
+    }
+
+    // a string literal with the given quote, start is where it is in the source
+    // it is only triple quoted if it was in the source, otherwise newlines are escaped
+    fn string(&self, s: &str, start: usize, quote: char) -> String {
+        if !self.triple_quoted.contains(&start) || quote != '"' {
+            return format!("{quote}{}{quote}", escape(s, quote));
+        }
+        let mut code = String::from("\"\"\"");
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\n' | '\t' => code.push(c),
+                // a quote only ends the string if there are three of them
+                '"' if matches!(chars.peek(), Some('"') | None) => code += "\\\"",
+                '"' => code.push(c),
+                _ => code += &escape(&c.to_string(), quote),
+            }
+        }
+        code + "\"\"\""
+    }
+}
+
+// the text with escape sequences for the quote, backslashes and control characters
+fn escape(text: &str, quote: char) -> String {
+    let mut code = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => code += "\\\\",
+            '\n' => code += "\\n",
+            '\t' => code += "\\t",
+            '\r' => code += "\\r",
+            c if c == quote => {
+                code.push('\\');
+                code.push(c);
+            }
+            c if c.is_control() => code += &format!("\\x{:02x}", c as u32),
+            c => code.push(c),
+        }
+    }
+    code
+}
@@ -1,204 +1,869 @@
-use std::{collections::HashMap, fmt};
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt,
+    fs::read_to_string,
+    io::Write,
+    panic,
+    path::Path,
+    rc::Rc,
+    thread,
+};
 
-use crate::common::{ast::*, py_error::*};
+pub mod heap;
+mod vm;
 
-#[derive(Debug, Clone, PartialEq)]
+use crate::{
+    builtins::{self, Args, Builtin},
+    common::{ast::*, py_error::*},
+    compiler::{self, Code},
+    file::File,
+    format::{convert, float_repr, format_value},
+    limits::{self, Limits, Quotas},
+    methods,
+    modules::{Module, Modules},
+    native::{self, sys},
+    optimizer, parser,
+    resolver::{self, Access, FunctionScope, Functions},
+    scanner,
+    streams::Streams,
+};
+
+// lists are shared, so changes through one variable are visible through all others
+pub type ListRef = Rc<RefCell<Vec<Value>>>;
+pub type FileRef = Rc<RefCell<File>>;
+// variable shared between a function and the functions nested in it, None until it is assigned
+pub type Cell = Rc<RefCell<Option<Value>>>;
+
+#[derive(Debug, Clone)]
 pub enum Value {
     Int(i128),
     Float(f64),
     String(String),
     Bool(bool),
-    List(Vec<Value>),
+    List(ListRef),
+    Function(Rc<Function>),
+    // builtin function or function of a native module
+    Builtin(&'static str, Builtin),
+    Module(Rc<Module>),
+    File(FileRef),
     None,
 }
 
-impl fmt::Display for Value {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Value::Int(n) => write!(f, "{n}"),
-            Value::Float(n) => write!(f, "{n}"),
-            Value::String(s) => write!(f, "{s}"),
-            Value::Bool(b) => write!(f, "{b}"),
-            Value::List(e) => {
-                let elems: Vec<String> = e.iter().map(|v| v.to_string()).collect();
-                write!(f, "[{}]", elems.join(", "))
-            }
-            Value::None => write!(f, "None"),
-        }
+impl Value {
+    pub fn list(elems: Vec<Value>) -> Self {
+        Value::List(heap::list(elems))
     }
-}
 
-impl Value {
-    fn to_bool(&self) -> bool {
+    pub fn to_bool(&self) -> bool {
         match self {
             Value::Int(n) if *n == 0 => false,
             Value::Float(n) if *n == 0.0 => false,
             Value::String(s) if s.is_empty() => false,
             Value::Bool(b) => *b,
-            Value::List(l) if l.is_empty() => false,
+            Value::List(l) if l.borrow().is_empty() => false,
             Value::None => false,
             _ => true,
         }
     }
+
+    // bools are treated as the ints 0 and 1 when compared with numbers
+    fn int_value(&self) -> Option<i128> {
+        match self {
+            Value::Int(n) => Some(*n),
+            Value::Bool(b) => Some(*b as i128),
+            _ => None,
+        }
+    }
+
+    fn is_number(&self) -> bool {
+        matches!(self, Value::Int(_) | Value::Float(_) | Value::Bool(_))
+    }
+
+    // python's ==, numbers are equal across int, float and bool (1 == 1.0 == True)
+    // lists compare their elements, the error is the RecursionError for lists nested too deep, e.g. containing themselves
+    pub fn equals(&self, other: &Value, limits: &mut Limits) -> Result<bool, String> {
+        Ok(match (self, other) {
+            (Value::String(a), Value::String(b)) => a == b,
+            // the same list is always equal to itself
+            (Value::List(a), Value::List(b)) if Rc::ptr_eq(a, b) => true,
+            (Value::List(a), Value::List(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.len() == b.len() && first_difference(&a, &b, limits)?.is_none()
+            }
+            (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+            (Value::Builtin(a, _), Value::Builtin(b, _)) => a == b,
+            (Value::Module(a), Value::Module(b)) => Rc::ptr_eq(a, b),
+            (Value::File(a), Value::File(b)) => Rc::ptr_eq(a, b),
+            (Value::None, Value::None) => true,
+            _ => self.compare(other, limits)? == Some(Ordering::Equal),
+        })
+    }
+
+    // python's ordering, None if the values can't be ordered (see Value::unorderable_types) or one of them is nan
+    pub fn compare(&self, other: &Value, limits: &mut Limits) -> Result<Option<Ordering>, String> {
+        Ok(match (self, other) {
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            // lists are compared by their first differing element, otherwise by length
+            (Value::List(a), Value::List(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                match first_difference(&a, &b, limits)? {
+                    Some(i) => a[i].compare(&b[i], limits)?,
+                    None => Some(a.len().cmp(&b.len())),
+                }
+            }
+            (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+            (Value::Float(x), n) => n
+                .int_value()
+                .and_then(|n| cmp_int_float(n, *x))
+                .map(Ordering::reverse),
+            (n, Value::Float(x)) => n.int_value().and_then(|n| cmp_int_float(n, *x)),
+            _ => match (self.int_value(), other.int_value()) {
+                (Some(a), Some(b)) => Some(a.cmp(&b)),
+                _ => None,
+            },
+        })
+    }
+
+    // the types of the values that <, <=, > and >= don't support, None if they can be ordered
+    // for lists these are the types of the first differing elements, like in python
+    pub fn unorderable_types(
+        &self,
+        other: &Value,
+        limits: &mut Limits,
+    ) -> Result<Option<(&'static str, &'static str)>, String> {
+        match (self, other) {
+            (Value::String(_), Value::String(_)) => Ok(None),
+            (Value::List(a), Value::List(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                match first_difference(&a, &b, limits)? {
+                    Some(i) => a[i].unorderable_types(&b[i], limits),
+                    None => Ok(None),
+                }
+            }
+            _ if self.is_number() && other.is_number() => Ok(None),
+            _ => Ok(Some((self.type_name(), other.type_name()))),
+        }
+    }
+
+    // identity for is, lists and functions are only the same object as themselves
+    // the other values are immutable, so they are the same if they are equal (like small ints in python)
+    pub fn is_same(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::List(a), Value::List(b)) => Rc::ptr_eq(a, b),
+            (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+            (Value::Module(a), Value::Module(b)) => Rc::ptr_eq(a, b),
+            (Value::File(a), Value::File(b)) => Rc::ptr_eq(a, b),
+            (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Builtin(a, _), Value::Builtin(b, _)) => a == b,
+            (Value::None, Value::None) => true,
+            _ => false,
+        }
+    }
+
+    // the elements when iterating over the value, None if it isn't iterable
+    // files give their remaining lines, reading them can fail
+    pub fn iterate(&self) -> Option<Result<Vec<Value>, String>> {
+        match self {
+            Value::List(l) => Some(Ok(l.borrow().clone())),
+            Value::String(s) => Some(Ok(s
+                .chars()
+                .map(|c| Value::String(c.to_string()))
+                .collect())),
+            Value::File(f) => Some(
+                f.borrow_mut()
+                    .readlines()
+                    .map(|lines| lines.into_iter().map(Value::String).collect()),
+            ),
+            _ => None,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::String(_) => "str",
+            Value::Bool(_) => "bool",
+            Value::List(_) => "list",
+            Value::Function(_) => "function",
+            Value::Builtin(..) => "builtin_function_or_method",
+            Value::Module(_) => "module",
+            Value::File(_) => "TextIOWrapper",
+            Value::None => "NoneType",
+        }
+    }
+
+    // like python's str(), only strings are shown differently than by repr
+    pub fn str(&self, limits: &mut Limits) -> Result<String, String> {
+        match self {
+            Value::String(s) => Ok(s.clone()),
+            v => v.repr(limits),
+        }
+    }
+
+    // like python's repr(), strings are quoted and floats always show that they are floats
+    // the limits are for lists, which can be nested deeply
+    pub fn repr(&self, limits: &mut Limits) -> Result<String, String> {
+        self.repr_nested(&mut HashSet::new(), limits)
+    }
+
+    // seen contains the lists that are currently being printed, so a list containing itself becomes [...]
+    fn repr_nested(
+        &self,
+        seen: &mut HashSet<*const RefCell<Vec<Value>>>,
+        limits: &mut Limits,
+    ) -> Result<String, String> {
+        Ok(match self {
+            Value::Int(n) => n.to_string(),
+            Value::Float(x) => float_repr(*x),
+            Value::String(s) => quote_string(s),
+            Value::Bool(true) => "True".to_owned(),
+            Value::Bool(false) => "False".to_owned(),
+            Value::List(l) => {
+                let ptr = Rc::as_ptr(l);
+                if seen.contains(&ptr) {
+                    return Ok("[...]".to_owned());
+                }
+                limits.enter_nested(" while getting the repr of an object")?;
+                seen.insert(ptr);
+                let elems: Result<Vec<String>, String> = l
+                    .borrow()
+                    .iter()
+                    .map(|v| v.repr_nested(seen, limits))
+                    .collect();
+                seen.remove(&ptr);
+                limits.leave();
+                format!("[{}]", elems?.join(", "))
+            }
+            Value::Function(func) => format!("<function {}>", func.name.name),
+            Value::Builtin(name, _) => format!("<built-in function {name}>"),
+            Value::Module(m) => format!("<module '{}'>", m.name),
+            Value::File(f) => {
+                let f = f.borrow();
+                format!("<_io.TextIOWrapper name='{}' mode='{}'>", f.name, f.mode)
+            }
+            Value::None => "None".to_owned(),
+        })
+    }
+
+    // like repr, but non-ascii chars are escaped as well
+    pub fn ascii(&self, limits: &mut Limits) -> Result<String, String> {
+        Ok(self
+            .repr(limits)?
+            .chars()
+            .map(|c| {
+                if c.is_ascii() {
+                    c.to_string()
+                } else {
+                    escape_char(c)
+                }
+            })
+            .collect())
+    }
+}
+
+// puts quotes around a string and escapes it, prefers single quotes like python
+fn quote_string(s: &str) -> String {
+    let quote = if s.contains('\'') && !s.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    let mut res = String::from(quote);
+    for c in s.chars() {
+        match c {
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if c == quote => {
+                res.push('\\');
+                res.push(c);
+            }
+            c if c.is_control() => res.push_str(&escape_char(c)),
+            c => res.push(c),
+        }
+    }
+    res.push(quote);
+    res
+}
+
+// \xNN, \uNNNN or \UNNNNNNNN depending on the size of the char
+fn escape_char(c: char) -> String {
+    match c as u32 {
+        n if n <= 0xff => format!("\\x{n:02x}"),
+        n if n <= 0xffff => format!("\\u{n:04x}"),
+        n => format!("\\U{n:08x}"),
+    }
+}
+
+// index of the first elements that aren't equal, None if one list starts with the other
+fn first_difference(
+    a: &[Value],
+    b: &[Value],
+    limits: &mut Limits,
+) -> Result<Option<usize>, String> {
+    limits.enter_nested(" in comparison")?;
+    let mut res = Ok(None);
+    for (i, (x, y)) in a.iter().zip(b).enumerate() {
+        match x.equals(y, limits) {
+            Ok(true) => (),
+            Ok(false) => {
+                res = Ok(Some(i));
+                break;
+            }
+            Err(e) => {
+                res = Err(e);
+                break;
+            }
+        }
+    }
+    limits.leave();
+    res
+}
+
+// compares exactly, converting the int to a float could round it, e.g. 2**53 + 1 to 2**53
+fn cmp_int_float(n: i128, x: f64) -> Option<Ordering> {
+    if x.is_nan() {
+        return None;
+    }
+    let floor = x.floor();
+    // outside of the ints, also for infinity
+    if floor >= i128::MAX as f64 {
+        return Some(Ordering::Less);
+    }
+    if floor < i128::MIN as f64 {
+        return Some(Ordering::Greater);
+    }
+    // n is either below the floor, above x, or equal to the floor and x is only bigger by its fraction
+    Some(match n.cmp(&(floor as i128)) {
+        Ordering::Equal if x > floor => Ordering::Less,
+        ord => ord,
+    })
 }
 
-#[derive(Clone)]
-struct Function {
+pub struct Function {
     name: Name,
     parameters: Vec<Name>,
-    body: Vec<Stmt>,
+    body: Body,
+    // the module the function was defined in
+    globals: Rc<Module>,
+    // the variables of enclosing functions the function uses, see FunctionScope::free
+    cells: Vec<Cell>,
 }
 
-impl Function {
-    fn arity(&self) -> usize {
-        self.parameters.len()
+// functions defined by the tree-walker share their statements with the syntax tree, the ones made by the vm their bytecode
+enum Body {
+    Ast(Rc<Vec<Stmt>>, Rc<FunctionScope>),
+    Code(Rc<Code>),
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<function {}>", self.name.name)
     }
+}
 
-    fn call(&self, args: Vec<Value>, encl: Environment) -> Result<Value, PyError> {
-        let vars: HashMap<String, Value> = self
-            .parameters
-            .iter()
-            .zip(args.iter())
-            .map(|(param, arg)| (param.name.clone(), arg.clone()))
+impl Function {
+    // matches positional and keyword arguments to the parameters
+    fn bind(
+        &self,
+        args: Vec<Value>,
+        kwargs: Vec<(String, Value)>,
+        call: &Name,
+    ) -> Result<Vec<Value>, PyError> {
+        let keywords: Vec<&str> = kwargs.iter().map(|(k, _)| k.as_str()).collect();
+        let order = match_arguments(&self.name.name, &self.parameters, args.len(), &keywords)
+            .map_err(|msg| PyError {
+                msg,
+                line: call.line,
+                column: call.column,
+            })?;
+        let mut values: Vec<Option<Value>> = args
+            .into_iter()
+            .chain(kwargs.into_iter().map(|(_, v)| v))
+            .map(Some)
             .collect();
+        // every argument is used once
+        Ok(order.into_iter().filter_map(|i| values[i].take()).collect())
+    }
 
+    fn call(&self, args: Vec<Value>, caller: &Interpreter) -> Result<Value, PyError> {
+        // the vm keeps the variables of the call in its frame, it only needs the module
+        let env = match &self.body {
+            Body::Ast(_, scope) => {
+                Environment::function(self.globals.clone(), scope.clone(), &self.cells)
+            }
+            Body::Code(_) => Environment::global(self.globals.clone()),
+        };
         let mut fun_int = Interpreter {
-            env: Environment {
-                enclosed_by: Some(Box::new(encl)),
-                funcs: HashMap::new(),
-                vars,
-            },
+            env,
+            streams: caller.streams.clone(),
+            modules: caller.modules.clone(),
+            limits: caller.limits.clone(),
+            options: caller.options,
         };
 
-        for st in self.body.clone() {
-            match fun_int.interpret_stmt(st) {
-                Ok(None) => continue,
-                Ok(Some((_, v))) => return Ok(v),
-                Err(e) => return Err(e),
+        let returned = match &self.body {
+            Body::Ast(body, _) => {
+                for (param, arg) in self.parameters.iter().zip(args) {
+                    fun_int.env.assign_var(&param.name, arg);
+                }
+                let mut returned = None;
+                for st in body.iter() {
+                    returned = fun_int.interpret_stmt(st)?;
+                    if returned.is_some() {
+                        break;
+                    }
+                }
+                returned
             }
-        }
-
-        Ok(Value::None)
+            Body::Code(code) => fun_int.execute(code, args, &self.cells)?,
+        };
+        Ok(returned.map_or(Value::None, |(_, v)| v))
     }
 }
 
-#[derive(Clone)]
+// the variables of the tree-walker, the resolver decides where each of them is kept
 struct Environment {
-    enclosed_by: Option<Box<Environment>>,
-    funcs: HashMap<String, Function>,
-    vars: HashMap<String, Value>,
+    globals: Rc<Module>,
+    // None at the top level of a module
+    scope: Option<Rc<FunctionScope>>,
+    // the functions defined at the top level of the module, the ones in a function are in its scope
+    functions: Functions,
+    // indexed like the Access of the variables
+    slots: Vec<Option<Value>>,
+    cells: Vec<Cell>,
 }
 
 impl Environment {
-    fn assign_var(&mut self, name: String, val: Value) {
-        self.vars.insert(name, val);
+    fn global(globals: Rc<Module>) -> Self {
+        Environment {
+            globals,
+            scope: None,
+            functions: Functions::new(),
+            slots: Vec::new(),
+            cells: Vec::new(),
+        }
     }
 
-    fn get_var(&self, var: Name) -> Result<Value, PyError> {
-        match self.vars.get(&var.name) {
-            Some(v) => Ok(v.clone()),
-            None => {
-                if let Some(e) = &self.enclosed_by {
-                    e.get_var(var)
-                } else {
-                    Err(PyError {
-                        msg: format!("NameError: name {} is not defined", var.name),
-                        line: var.line,
-                        column: var.column,
-                    })
-                }
+    // every call gets new cells for its own shared variables, the others come from the enclosing functions
+    fn function(globals: Rc<Module>, scope: Rc<FunctionScope>, free: &[Cell]) -> Self {
+        let own = (0..scope.own_cells).map(|_| heap::cell());
+        Environment {
+            globals,
+            slots: vec![None; scope.slots.len()],
+            cells: own.chain(free.iter().cloned()).collect(),
+            scope: Some(scope),
+            functions: Functions::new(),
+        }
+    }
+
+    fn access(&self, name: &str) -> Access {
+        resolver::access(self.scope.as_deref(), name)
+    }
+
+    fn assign_var(&mut self, name: &str, val: Value) {
+        match self.access(name) {
+            Access::Local(i) => self.slots[i] = Some(val),
+            Access::Cell(i) => *self.cells[i].borrow_mut() = Some(val),
+            Access::Global => {
+                self.globals.vars.borrow_mut().insert(name.to_owned(), val);
             }
         }
     }
 
-    fn assign_fun(&mut self, name: Name, parameters: Vec<Name>, body: Vec<Stmt>) {
-        let n = name.name.clone();
+    fn get_var(&self, var: &Name) -> Result<Value, PyError> {
+        let access = self.access(&var.name);
+        let val = match access {
+            Access::Local(i) => self.slots[i].clone(),
+            Access::Cell(i) => self.cells[i].borrow().clone(),
+            Access::Global => lookup_global(&self.globals, &var.name),
+        };
+        val.ok_or_else(|| {
+            let own_cells = self.scope.as_ref().map_or(0, |s| s.own_cells);
+            unbound(&var.name, access, own_cells, var.line, var.column)
+        })
+    }
+
+    fn assign_fun(&mut self, name: &Name, parameters: &[Name], body: &Rc<Vec<Stmt>>) {
+        let functions = self.scope.as_ref().map_or(&self.functions, |s| &s.nested);
+        // resolved together with the code around the def, unless that code wasn't resolved as a whole
+        let scope = match functions.get(&name.span.start) {
+            Some(scope) => scope.clone(),
+            None => Rc::new(FunctionScope::new(parameters, body, self.scope.as_deref())),
+        };
+        let cells = scope
+            .free()
+            .iter()
+            .map(|n| match self.access(n) {
+                Access::Cell(i) => self.cells[i].clone(),
+                _ => unreachable!("free variables are cells of the enclosing function"),
+            })
+            .collect();
         let f = Function {
-            name,
-            parameters,
-            body,
+            name: name.clone(),
+            parameters: parameters.to_vec(),
+            body: Body::Ast(body.clone(), scope),
+            globals: self.globals.clone(),
+            cells,
         };
-        self.funcs.insert(n, f);
+        self.assign_var(&name.name, Value::Function(heap::function(f)));
     }
+}
 
-    fn get_fun(&self, fun: Name) -> Result<Function, PyError> {
-        match self.funcs.get(&fun.name) {
-            Some(f) => Ok(f.clone()),
-            None => {
-                if let Some(e) = &self.enclosed_by {
-                    e.get_fun(fun)
-                } else {
-                    Err(PyError {
-                        msg: format!("NameError: name {} is not defined", fun.name),
-                        line: fun.line,
-                        column: fun.column,
-                    })
-                }
-            }
-        }
+// builtins are only looked up last, so they can be shadowed
+fn lookup_global(globals: &Module, name: &str) -> Option<Value> {
+    match globals.vars.borrow().get(name) {
+        Some(v) => Some(v.clone()),
+        None => builtins::lookup(name).map(|(name, f)| Value::Builtin(name, f)),
     }
 }
 
-// entry point, goes through all statements and prints errors
-pub fn interpret(stmts: Vec<Stmt>) {
+// error for a variable without a value, own_cells is the number of cells of the running function that are its own
+fn unbound(name: &str, access: Access, own_cells: usize, line: u64, column: u64) -> PyError {
+    let msg = match access {
+        Access::Cell(i) if i >= own_cells => format!(
+            "NameError: cannot access free variable '{name}' where it is not associated with a value in enclosing scope"
+        ),
+        Access::Local(_) | Access::Cell(_) => format!(
+            "UnboundLocalError: cannot access local variable '{name}' where it is not associated with a value"
+        ),
+        Access::Global => format!("NameError: name {name} is not defined"),
+    };
+    PyError { msg, line, column }
+}
+
+// how scripts and imported modules are run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Engine {
+    // walks the AST directly, kept as the reference for the vm
+    TreeWalker,
+    // compiles to bytecode first, see compiler.rs and interpreter/vm.rs
+    Bytecode,
+}
+
+// set with the command line options, the same for the script and the modules it imports
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    pub engine: Engine,
+    // see optimizer.rs
+    pub optimize: bool,
+    pub quotas: Quotas,
+}
+
+// entry point, scans, parses and runs the code of a script, prints errors and returns the exit code
+// imported modules are searched relative to script_dir, argv is for sys.argv
+// all of it happens on a thread of its own with the stack size limits.rs expects, whichever thread the host calls this on
+pub fn interpret(
+    code: String,
+    streams: Streams,
+    script_dir: &Path,
+    argv: Vec<String>,
+    options: Options,
+) -> i32 {
+    thread::scope(|s| {
+        thread::Builder::new()
+            .stack_size(limits::STACK_SIZE)
+            .spawn_scoped(s, || run_main(code, streams, script_dir, argv, options))
+            .expect("the thread for the script could not be started")
+            .join()
+            // the panic message was already printed by the thread
+            .unwrap_or_else(|e| panic::resume_unwind(e))
+    })
+}
+
+fn run_main(
+    code: String,
+    streams: Streams,
+    script_dir: &Path,
+    argv: Vec<String>,
+    options: Options,
+) -> i32 {
+    // stop running if there was an error
+    let tokens;
+    if let Some(t) = scanner::scan(code) {
+        tokens = t;
+    } else {
+        return 1;
+    }
+    // for t in &tokens {
+    //     println!("{:?}, {}, {}", t.token_type, t.line, t.column);
+    // }
+
+    let stmts;
+    if let Some(s) = parser::parse(tokens) {
+        stmts = s;
+    } else {
+        return 1;
+    }
+    // println!("\n{stmts:?}\n");
+    let stmts = if options.optimize {
+        optimizer::optimize(stmts)
+    } else {
+        stmts
+    };
+
+    let main = heap::module("__main__".to_owned(), HashMap::new());
     let mut int = Interpreter {
-        env: Environment {
-            enclosed_by: None,
-            funcs: HashMap::new(),
-            vars: HashMap::new(),
-        },
+        env: Environment::global(main),
+        streams: Rc::new(RefCell::new(streams)),
+        modules: Rc::new(RefCell::new(Modules::new(script_dir, argv))),
+        limits: Rc::new(RefCell::new(Limits::new(options.quotas))),
+        options,
     };
-    for st in stmts {
-        match int.interpret_stmt(st) {
-            Ok(None) => continue,
-            Ok(Some((l, _))) => {
-                let e = PyError {
-                    msg: "SyntaxError: return statement outside of function".to_owned(),
-                    line: l.line,
-                    column: l.column,
-                };
-                println!("{e}");
-                break;
-            }
-            Err(e) => {
-                println!("{e}");
-                break;
+    let code = match int.run(&stmts) {
+        Ok(()) => 0,
+        Err(e) => match sys::exit_code(&e) {
+            Some(code) => code,
+            None => {
+                int.write_error(e);
+                1
             }
-        }
-    }
+        },
+    };
+    // output without a newline at the end could still be buffered
+    let _ = int.streams.borrow_mut().stdout.flush();
+    code
 }
 
-struct Interpreter {
+pub struct Interpreter {
     env: Environment,
+    // shared with the interpreters of function calls and imported modules
+    pub streams: Rc<RefCell<Streams>>,
+    modules: Rc<RefCell<Modules>>,
+    // quotas and recursion depth, shared like the streams
+    pub limits: Rc<RefCell<Limits>>,
+    options: Options,
 }
 
 impl Interpreter {
-    fn interpret_stmt(&mut self, stmt: Stmt) -> Result<Option<(Location, Value)>, PyError> {
+    // runs the top level statements of a script or module
+    fn run(&mut self, stmts: &[Stmt]) -> Result<(), PyError> {
+        let returned = match self.options.engine {
+            Engine::TreeWalker => {
+                self.env.functions = resolver::module_functions(stmts);
+                let mut returned = None;
+                for st in stmts {
+                    returned = self.interpret_stmt(st)?;
+                    if returned.is_some() {
+                        break;
+                    }
+                }
+                returned
+            }
+            Engine::Bytecode => {
+                let code = compiler::compile(stmts);
+                // println!("{code:?}");
+                self.execute(&code, Vec::new(), &[])?
+            }
+        };
+        match returned {
+            Some((l, _)) => Err(PyError {
+                msg: "SyntaxError: return statement outside of function".to_owned(),
+                line: l.line,
+                column: l.column,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // runs a module the first time it is imported, afterwards it comes from the cache
+    fn import_module(&mut self, name: &Name) -> Result<Rc<Module>, PyError> {
+        let error = |msg: String| PyError {
+            msg,
+            line: name.line,
+            column: name.column,
+        };
+        let path = {
+            let mut modules = self.modules.borrow_mut();
+            if let Some(m) = modules.loaded.get(&name.name) {
+                return Ok(m.clone());
+            }
+            // native modules come first, like python's builtin modules
+            if let Some(init) = native::lookup(&name.name) {
+                let vars = init(&modules)
+                    .into_iter()
+                    .map(|(n, v)| (n.to_owned(), v))
+                    .collect();
+                let module = heap::module(name.name.clone(), vars);
+                modules.loaded.insert(name.name.clone(), module.clone());
+                return Ok(module);
+            }
+            if modules.loading.contains(&name.name) {
+                return Err(error(format!(
+                    "ImportError: cannot import module '{}' (circular import)",
+                    name.name
+                )));
+            }
+            modules.find(&name.name).ok_or_else(|| {
+                error(format!(
+                    "ModuleNotFoundError: No module named '{}'",
+                    name.name
+                ))
+            })?
+        };
+
+        let code = read_to_string(&path)
+            .map_err(|e| error(format!("ImportError: {}: {e}", path.display())))?;
+        // the scanner and parser already printed their errors
+        let stmts = scanner::scan(code).and_then(parser::parse).ok_or_else(|| {
+            error(format!(
+                "SyntaxError: invalid syntax in module '{}'",
+                name.name
+            ))
+        })?;
+        let stmts = if self.options.optimize {
+            optimizer::optimize(stmts)
+        } else {
+            stmts
+        };
+
+        let module = heap::module(name.name.clone(), HashMap::new());
+        let mut mod_int = Interpreter {
+            env: Environment::global(module.clone()),
+            streams: self.streams.clone(),
+            modules: self.modules.clone(),
+            limits: self.limits.clone(),
+            options: self.options,
+        };
+        self.limits.borrow_mut().enter(name)?;
+        self.modules.borrow_mut().loading.push(name.name.clone());
+        let res = mod_int.run(&stmts);
+        self.modules.borrow_mut().loading.pop();
+        self.limits.borrow_mut().leave();
+        // the line and column of the error are in the module, the ones of the import are printed after them
+        res.map_err(|e| {
+            error(format!(
+                "{}\n    Line {}, Column {} in module '{}' ({})",
+                e.msg,
+                e.line,
+                e.column,
+                name.name,
+                path.display()
+            ))
+        })?;
+
+        self.modules
+            .borrow_mut()
+            .loaded
+            .insert(name.name.clone(), module.clone());
+        Ok(module)
+    }
+
+    // calls __enter__ of the context manager of a with statement
+    fn enter_with(&mut self, manager: &Value, loc: &Location) -> Result<Value, PyError> {
+        if !methods::has_method(manager, "__enter__") || !methods::has_method(manager, "__exit__") {
+            return Err(PyError {
+                msg: format!(
+                    "TypeError: '{}' object does not support the context manager protocol",
+                    manager.type_name()
+                ),
+                line: loc.line,
+                column: loc.column,
+            });
+        }
+        let args = Args {
+            positional: Vec::new(),
+            keyword: Vec::new(),
+        };
+        methods::call_method(
+            self,
+            manager.clone(),
+            &special_method("__enter__", loc),
+            args,
+        )
+    }
+
+    // there are no exception objects, so __exit__ always gets None for the exception
+    fn exit_with(&mut self, manager: Value, loc: &Location) -> Result<(), PyError> {
+        let args = Args {
+            positional: vec![Value::None, Value::None, Value::None],
+            keyword: Vec::new(),
+        };
+        methods::call_method(self, manager, &special_method("__exit__", loc), args)?;
+        Ok(())
+    }
+
+    fn get_attribute(&self, object: Value, name: &Name) -> Result<Value, PyError> {
+        let error = |msg: String| PyError {
+            msg,
+            line: name.line,
+            column: name.column,
+        };
+        match object {
+            Value::File(f) => {
+                let f = f.borrow();
+                match name.name.as_str() {
+                    "name" => Ok(Value::String(f.name.clone())),
+                    "mode" => Ok(Value::String(f.mode.clone())),
+                    "closed" => Ok(Value::Bool(f.closed())),
+                    _ => Err(error(format!(
+                        "AttributeError: '_io.TextIOWrapper' object has no attribute '{}'",
+                        name.name
+                    ))),
+                }
+            }
+            Value::Module(m) => match m.vars.borrow().get(&name.name) {
+                Some(v) => Ok(v.clone()),
+                None => Err(error(format!(
+                    "AttributeError: module '{}' has no attribute '{}'",
+                    m.name, name.name
+                ))),
+            },
+            v => Err(error(format!(
+                "AttributeError: '{}' object has no attribute '{}'",
+                v.type_name(),
+                name.name
+            ))),
+        }
+    }
+
+    // like python's tracebacks, runtime errors go to stderr
+    fn write_error(&self, e: PyError) {
+        let mut streams = self.streams.borrow_mut();
+        // anything printed before has to come first
+        let _ = streams.stdout.flush();
+        // if even stderr fails there is nowhere left to report it
+        let _ = writeln!(streams.stderr, "{e}");
+    }
+
+    fn interpret_stmt(&mut self, stmt: &Stmt) -> Result<Option<(Location, Value)>, PyError> {
+        if let Some(loc) = stmt_location(stmt) {
+            self.limits.borrow_mut().step(loc.line, loc.column)?;
+        }
         match stmt {
             Stmt::Expr(e) => {
                 self.eval_expr(e)?;
                 Ok(None)
             }
-            Stmt::Print(e) => {
-                let val = self.eval_expr(e)?;
-                println!("{val}");
-                Ok(None)
-            }
             Stmt::AssignVar(n, e) => {
                 let val = self.eval_expr(e)?;
-                self.env.assign_var(n.name, val);
+                self.env.assign_var(&n.name, val);
                 Ok(None)
             }
             Stmt::AssignList(n, i, e) => {
-                // NOTE: not very pretty as well but does its job
-                // also doesn't work with multi dimensional lists since it doesn't actually modify the list but reassigns it
-                let (mut list, idx) = self.list_and_idx(n.clone(), i)?;
+                // NOTE: lists are shared, so changing them in place is visible through every variable holding them
+                // still only works with a variable as the target, so no multi dimensional lists
+                if let Expr::Slice(start, stop, step, _) = i {
+                    let bounds = [start, stop, step].map(Option::as_deref);
+                    let slice = self.eval_slice(bounds, n.line, n.column)?;
+                    let list = self.list_var(n)?;
+                    let val = self.eval_expr(e)?;
+                    assign_slice(&list, slice, val, n.line, n.column)?;
+                    return Ok(None);
+                }
+                let (list, idx) = self.list_and_idx(n, i)?;
                 let val = self.eval_expr(e)?;
-                list[idx as usize] = val;
-                self.env.assign_var(n.name, Value::List(list));
+                set_item(&list, idx, val, n.line, n.column)?;
                 Ok(None)
             }
-            Stmt::If(c, t, e) => {
+            Stmt::If(_, c, t, e) => {
                 let cond = self.eval_expr(c)?.to_bool();
                 if cond {
                     for st in t {
@@ -219,9 +884,10 @@ impl Interpreter {
                 }
                 Ok(None)
             }
-            Stmt::While(c, b) => {
-                while self.eval_expr(c.clone())?.to_bool() {
-                    for st in b.clone() {
+            Stmt::While(l, c, b) => {
+                while self.eval_expr(c)?.to_bool() {
+                    self.limits.borrow_mut().step(l.line, l.column)?;
+                    for st in b {
                         let res = self.interpret_stmt(st);
                         match res {
                             Ok(None) => continue,
@@ -231,36 +897,117 @@ impl Interpreter {
                 }
                 Ok(None)
             }
-            Stmt::FunDecl(n, p, b) => {
+            Stmt::With(l, e, a, b) => self.run_with(l, e, a.as_ref(), b),
+            Stmt::FunDecl(_, n, p, b) => {
                 self.env.assign_fun(n, p, b);
                 Ok(None)
             }
+            Stmt::Import(_, names) => {
+                for (name, alias) in names {
+                    let module = self.import_module(name)?;
+                    let bound = alias.as_ref().unwrap_or(name);
+                    self.env.assign_var(&bound.name, Value::Module(module));
+                }
+                Ok(None)
+            }
+            Stmt::FromImport(_, module, names) => {
+                let module = self.import_module(module)?;
+                for (name, alias) in names {
+                    let val = import_from(&module, name)?;
+                    let bound = alias.as_ref().unwrap_or(name);
+                    self.env.assign_var(&bound.name, val);
+                }
+                Ok(None)
+            }
             Stmt::Return(l, e) => {
                 if let Some(ex) = e {
                     let val = self.eval_expr(ex)?;
-                    Ok(Some((l, val)))
+                    Ok(Some((l.clone(), val)))
                 } else {
-                    Ok(Some((l, Value::None)))
+                    Ok(Some((l.clone(), Value::None)))
                 }
             }
         }
     }
 
-    fn eval_expr(&mut self, expr: Expr) -> Result<Value, PyError> {
+    // __exit__() is called however the body is left, the error from the body (if any) is passed on after it
+    fn run_with(
+        &mut self,
+        loc: &Location,
+        manager: &Expr,
+        alias: Option<&Name>,
+        body: &[Stmt],
+    ) -> Result<Option<(Location, Value)>, PyError> {
+        let manager = self.eval_expr(manager)?;
+        let entered = self.enter_with(&manager, loc)?;
+        if let Some(alias) = alias {
+            self.env.assign_var(&alias.name, entered);
+        }
+        let mut res = Ok(None);
+        for st in body {
+            res = self.interpret_stmt(st);
+            if !matches!(res, Ok(None)) {
+                break;
+            }
+        }
+        self.exit_with(manager, loc)?;
+        res
+    }
+
+    fn eval_expr(&mut self, expr: &Expr) -> Result<Value, PyError> {
         match expr {
-            Expr::Unary(op, e) => self.eval_unary(op, *e),
-            Expr::Binary(e1, op, e2) => self.eval_binary(*e1, op, *e2),
-            Expr::Grouping(e) => self.eval_expr(*e),
-            Expr::Literal(l) => self.eval_literal(l),
+            Expr::Unary(op, e) => self.eval_unary(op, e),
+            Expr::Binary(e1, op, e2) => self.eval_binary(e1, op, e2),
+            Expr::Compare(e, rest) => self.eval_compare(e, rest),
+            Expr::Grouping(e, _) => self.eval_expr(e),
+            Expr::Literal(l, _) => self.eval_literal(l),
             Expr::Variable(n) => self.env.get_var(n),
-            Expr::Call(n, a) => self.eval_call(n, a),
-            Expr::ListAccess(n, i) => self.eval_access(n, *i),
+            Expr::Call(n, a, k, _) => self.eval_call(n, a, k),
+            Expr::ListAccess(t, i, l, _) => self.eval_access(t, i, l),
+            Expr::MethodCall(o, n, a, k, _) => self.eval_method_call(o, n, a, k),
+            Expr::Attribute(o, n) => {
+                let object = self.eval_expr(o)?;
+                self.get_attribute(object, n)
+            }
+            Expr::FString(p, _) => self.eval_fstring(p),
+            // the parser only allows slices inside of subscripts, where eval_access handles them
+            Expr::Slice(..) => unreachable!("slice outside of subscript"),
         }
     }
 
-    fn eval_unary(&mut self, op: UnOp, expr: Expr) -> Result<Value, PyError> {
+    fn eval_fstring(&mut self, parts: &[FStringPart]) -> Result<Value, PyError> {
+        let mut res = String::new();
+        for part in parts {
+            match part {
+                FStringPart::Literal(s) => res.push_str(s),
+                FStringPart::Field(ex, conv, spec, loc) => {
+                    let error = |msg| PyError {
+                        msg,
+                        line: loc.line,
+                        column: loc.column,
+                    };
+                    let val = self.eval_expr(ex)?;
+                    let val = convert(val, *conv, &mut self.limits.borrow_mut()).map_err(error)?;
+                    // the spec can contain fields itself
+                    let spec = match self.eval_fstring(spec)? {
+                        Value::String(s) => s,
+                        _ => unreachable!("eval_fstring always returns a string"),
+                    };
+                    let formatted =
+                        format_value(&val, &spec, &mut self.limits.borrow_mut()).map_err(error)?;
+                    res.push_str(&formatted);
+                }
+            }
+        }
+        Ok(Value::String(res))
+    }
+
+    fn eval_unary(&mut self, op: &UnOp, expr: &Expr) -> Result<Value, PyError> {
         let right = self.eval_expr(expr)?;
+        Self::unary_op(op.clone(), right)
+    }
 
+    pub fn unary_op(op: UnOp, right: Value) -> Result<Value, PyError> {
         match (op.ty, right) {
             (UnOpType::Minus, Value::Int(n)) => Ok(Value::Int(-n)),
             (UnOpType::Minus, Value::Float(n)) => Ok(Value::Float(-n)),
@@ -281,13 +1028,56 @@ impl Interpreter {
                 line: op.line,
                 column: op.column,
             }),
+            (UnOpType::Minus, v) => Err(PyError {
+                msg: format!(
+                    "TypeError: Can't apply unary operator - to {}",
+                    v.type_name()
+                ),
+                line: op.line,
+                column: op.column,
+            }),
         }
     }
 
-    fn eval_binary(&mut self, ex1: Expr, op: BiOp, ex2: Expr) -> Result<Value, PyError> {
+    fn eval_binary(&mut self, ex1: &Expr, op: &BiOp, ex2: &Expr) -> Result<Value, PyError> {
         let left = self.eval_expr(ex1)?;
         let right = self.eval_expr(ex2)?;
+        Self::binary_op(left, op.clone(), right, &mut self.limits.borrow_mut())
+    }
+
+    // a < b < c is a < b and b < c, but b is only evaluated once and c not at all if a < b is false
+    fn eval_compare(&mut self, first: &Expr, rest: &[(BiOp, Expr)]) -> Result<Value, PyError> {
+        let mut left = self.eval_expr(first)?;
+        let mut res = Value::Bool(true);
+        for (op, ex) in rest {
+            let right = self.eval_expr(ex)?;
+            res = Self::binary_op(
+                left,
+                op.clone(),
+                right.clone(),
+                &mut self.limits.borrow_mut(),
+            )?;
+            if !res.to_bool() {
+                break;
+            }
+            left = right;
+        }
+        Ok(res)
+    }
 
+    // applies a binary operator to two evaluated operands
+    // the limits are for comparing lists, which can be nested deeply
+    pub fn binary_op(
+        left: Value,
+        op: BiOp,
+        right: Value,
+        limits: &mut Limits,
+    ) -> Result<Value, PyError> {
+        let error = |msg| PyError {
+            msg,
+            line: op.line,
+            column: op.column,
+        };
         match op.ty {
             BiOpType::Plus => match (left, right) {
                 (Value::Int(a), Value::Int(b)) => {
@@ -308,11 +1098,14 @@ impl Interpreter {
                 (Value::Bool(a), Value::Bool(b)) => Ok(Value::Int(a as i128 + b as i128)),
                 (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{a}{b}"))),
                 (Value::List(a), Value::List(b)) => {
-                    // TODO: better way???
-                    let mut a = a.clone();
-                    let mut b = b.clone();
-                    a.append(&mut b);
-                    Ok(Value::List(a))
+                    // always a new list, a and b stay unchanged
+                    let elems = a
+                        .borrow()
+                        .iter()
+                        .chain(b.borrow().iter())
+                        .cloned()
+                        .collect();
+                    Ok(Value::list(elems))
                 }
                 _ => Err(PyError {
                     msg: "TypeError: Can't apply binary operator + here".to_owned(),
@@ -388,74 +1181,31 @@ impl Interpreter {
                     column: op.column,
                 }),
             },
-            BiOpType::DoubleEqual => match (left, right) {
-                (Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a == b)),
-                (Value::Int(a), Value::Float(b)) => Ok(Value::Bool(a as f64 == b)),
-                (Value::Float(a), Value::Int(b)) => Ok(Value::Bool(a == b as f64)),
-                (Value::Float(a), Value::Float(b)) => Ok(Value::Bool(a == b)),
-                (Value::String(a), Value::String(b)) => Ok(Value::Bool(a == b)),
-                (Value::Bool(a), Value::Bool(b)) => Ok(Value::Bool(a == b)),
-                (Value::List(a), Value::List(b)) => Ok(Value::Bool(a == b)),
-                (Value::None, Value::None) => Ok(Value::Bool(true)),
-                _ => Ok(Value::Bool(false)),
-                // TODO: in python 1 == True and 0 == False, but other numbers are not equal to either, maybe implement
-            },
-            BiOpType::NotEqual => match (left, right) {
-                (Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a != b)),
-                (Value::Int(a), Value::Float(b)) => Ok(Value::Bool(a as f64 != b)),
-                (Value::Float(a), Value::Int(b)) => Ok(Value::Bool(a != b as f64)),
-                (Value::Float(a), Value::Float(b)) => Ok(Value::Bool(a != b)),
-                (Value::String(a), Value::String(b)) => Ok(Value::Bool(a != b)),
-                (Value::Bool(a), Value::Bool(b)) => Ok(Value::Bool(a != b)),
-                (Value::List(a), Value::List(b)) => Ok(Value::Bool(a != b)),
-                (Value::None, Value::None) => Ok(Value::Bool(false)),
-                _ => Ok(Value::Bool(true)),
-                // TODO: see double equal above
-            },
-            BiOpType::Greater => match (left, right) {
-                (Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a > b)),
-                (Value::Int(a), Value::Float(b)) => Ok(Value::Bool(a as f64 > b)),
-                (Value::Float(a), Value::Int(b)) => Ok(Value::Bool(a > b as f64)),
-                (Value::Float(a), Value::Float(b)) => Ok(Value::Bool(a > b)),
-                _ => Err(PyError {
-                    msg: "TypeError: Can't apply binary operator > here".to_owned(),
-                    line: op.line,
-                    column: op.column,
-                }),
-            },
-            BiOpType::GreaterEqual => match (left, right) {
-                (Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a >= b)),
-                (Value::Int(a), Value::Float(b)) => Ok(Value::Bool(a as f64 >= b)),
-                (Value::Float(a), Value::Int(b)) => Ok(Value::Bool(a >= b as f64)),
-                (Value::Float(a), Value::Float(b)) => Ok(Value::Bool(a >= b)),
-                _ => Err(PyError {
-                    msg: "TypeError: Can't apply binary operator >= here".to_owned(),
-                    line: op.line,
-                    column: op.column,
-                }),
-            },
-            BiOpType::Less => match (left, right) {
-                (Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a < b)),
-                (Value::Int(a), Value::Float(b)) => Ok(Value::Bool((a as f64) < b)),
-                (Value::Float(a), Value::Int(b)) => Ok(Value::Bool(a < b as f64)),
-                (Value::Float(a), Value::Float(b)) => Ok(Value::Bool(a < b)),
-                _ => Err(PyError {
-                    msg: "TypeError: Can't apply binary operator < here".to_owned(),
-                    line: op.line,
-                    column: op.column,
-                }),
-            },
-            BiOpType::LessEqual => match (left, right) {
-                (Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a <= b)),
-                (Value::Int(a), Value::Float(b)) => Ok(Value::Bool(a as f64 <= b)),
-                (Value::Float(a), Value::Int(b)) => Ok(Value::Bool(a <= b as f64)),
-                (Value::Float(a), Value::Float(b)) => Ok(Value::Bool(a <= b)),
-                _ => Err(PyError {
-                    msg: "TypeError: Can't apply binary operator <= here".to_owned(),
-                    line: op.line,
-                    column: op.column,
-                }),
-            },
+            BiOpType::DoubleEqual => Ok(Value::Bool(left.equals(&right, limits).map_err(error)?)),
+            BiOpType::NotEqual => Ok(Value::Bool(!left.equals(&right, limits).map_err(error)?)),
+            BiOpType::Greater | BiOpType::GreaterEqual | BiOpType::Less | BiOpType::LessEqual => {
+                if let Some((a, b)) = left.unorderable_types(&right, limits).map_err(error)? {
+                    return Err(error(format!(
+                        "TypeError: '{op:?}' not supported between instances of '{a}' and '{b}'"
+                    )));
+                }
+                // nan isn't ordered, every comparison with it is false
+                let ord = left.compare(&right, limits).map_err(error)?;
+                Ok(Value::Bool(matches!(
+                    (op.ty, ord),
+                    (BiOpType::Greater, Some(Ordering::Greater))
+                        | (
+                            BiOpType::GreaterEqual,
+                            Some(Ordering::Greater | Ordering::Equal)
+                        )
+                        | (BiOpType::Less, Some(Ordering::Less))
+                        | (BiOpType::LessEqual, Some(Ordering::Less | Ordering::Equal))
+                )))
+            }
+            BiOpType::In => Ok(Value::Bool(contains(&right, &left, &op, limits)?)),
+            BiOpType::NotIn => Ok(Value::Bool(!contains(&right, &left, &op, limits)?)),
+            BiOpType::Is => Ok(Value::Bool(left.is_same(&right))),
+            BiOpType::IsNot => Ok(Value::Bool(!left.is_same(&right))),
             BiOpType::And => {
                 if !left.to_bool() {
                     Ok(left)
@@ -473,11 +1223,11 @@ impl Interpreter {
         }
     }
 
-    fn eval_literal(&mut self, lit: Lit) -> Result<Value, PyError> {
+    fn eval_literal(&mut self, lit: &Lit) -> Result<Value, PyError> {
         match lit {
-            Lit::Int(n) => Ok(Value::Int(n.into())),
-            Lit::Float(n) => Ok(Value::Float(n)),
-            Lit::String(s) => Ok(Value::String(s)),
+            Lit::Int(n) => Ok(Value::Int((*n).into())),
+            Lit::Float(n) => Ok(Value::Float(*n)),
+            Lit::String(s) => Ok(Value::String(s.clone())),
             Lit::True => Ok(Value::Bool(true)),
             Lit::False => Ok(Value::Bool(false)),
             Lit::List(elems) => {
@@ -486,85 +1236,517 @@ impl Interpreter {
                     let el = self.eval_expr(e)?;
                     list.push(el);
                 }
-                Ok(Value::List(list))
+                Ok(Value::list(list))
             }
             Lit::None => Ok(Value::None),
         }
     }
 
-    fn eval_call(&mut self, name: Name, arguments: Vec<Expr>) -> Result<Value, PyError> {
-        let f = self.env.get_fun(name.clone())?;
+    fn eval_call(
+        &mut self,
+        name: &Name,
+        arguments: &[Expr],
+        keywords: &[(Name, Expr)],
+    ) -> Result<Value, PyError> {
+        let callee = self.env.get_var(name)?;
+        let args = self.eval_args(arguments)?;
+        let kwargs = self.eval_kwargs(keywords)?;
+        self.call_value(callee, args, kwargs, name)
+    }
+
+    // calls a function value, name is the called name used for error locations
+    pub fn call_value(
+        &mut self,
+        callee: Value,
+        args: Vec<Value>,
+        kwargs: Vec<(String, Value)>,
+        name: &Name,
+    ) -> Result<Value, PyError> {
+        match callee {
+            Value::Function(f) => {
+                let args = f.bind(args, kwargs, name)?;
+                self.limits.borrow_mut().enter(name)?;
+                let res = f.call(args, self);
+                self.limits.borrow_mut().leave();
+                res
+            }
+            Value::Builtin(_, builtin) => {
+                let args = Args {
+                    positional: args,
+                    keyword: kwargs,
+                };
+                builtin(self, args, name)
+            }
+            v => Err(PyError {
+                msg: format!("TypeError: '{}' object is not callable", v.type_name()),
+                line: name.line,
+                column: name.column,
+            }),
+        }
+    }
+
+    fn eval_method_call(
+        &mut self,
+        object: &Expr,
+        name: &Name,
+        arguments: &[Expr],
+        keywords: &[(Name, Expr)],
+    ) -> Result<Value, PyError> {
+        let object = self.eval_expr(object)?;
+        // functions of modules are called like methods
+        if let Value::Module(_) = object {
+            let callee = self.get_attribute(object, name)?;
+            let args = self.eval_args(arguments)?;
+            let kwargs = self.eval_kwargs(keywords)?;
+            return self.call_value(callee, args, kwargs, name);
+        }
+        let args = Args {
+            positional: self.eval_args(arguments)?,
+            keyword: self.eval_kwargs(keywords)?,
+        };
+        methods::call_method(self, object, name, args)
+    }
+
+    fn eval_args(&mut self, arguments: &[Expr]) -> Result<Vec<Value>, PyError> {
         let mut args = Vec::new();
         for arg in arguments {
             args.push(self.eval_expr(arg)?);
         }
+        Ok(args)
+    }
 
-        if f.arity() != args.len() {
-            return Err(PyError {
-                msg: format!(
-                    "TypeError: {} takes {} positional arguments but {} were given",
-                    f.name.name,
-                    f.arity(),
-                    args.len()
-                ),
-                line: name.line,
-                column: name.column,
-            });
+    fn eval_kwargs(&mut self, keywords: &[(Name, Expr)]) -> Result<Vec<(String, Value)>, PyError> {
+        let mut kwargs: Vec<(String, Value)> = Vec::new();
+        for (key, ex) in keywords {
+            if kwargs.iter().any(|(k, _)| *k == key.name) {
+                return Err(PyError {
+                    msg: format!("SyntaxError: keyword argument repeated: {}", key.name),
+                    line: key.line,
+                    column: key.column,
+                });
+            }
+            let val = self.eval_expr(ex)?;
+            kwargs.push((key.name.clone(), val));
         }
+        Ok(kwargs)
+    }
 
-        f.call(args, self.env.clone())
+    fn eval_access(
+        &mut self,
+        target: &Expr,
+        idx_ex: &Expr,
+        loc: &Location,
+    ) -> Result<Value, PyError> {
+        let target = self.eval_expr(target)?;
+        if let Expr::Slice(start, stop, step, _) = idx_ex {
+            let bounds = [start, stop, step].map(Option::as_deref);
+            let slice = self.eval_slice(bounds, loc.line, loc.column)?;
+            return get_slice(target, slice, loc);
+        }
+        let idx_val = self.eval_expr(idx_ex)?;
+        get_item(target, idx_val, loc)
     }
 
-    fn eval_access(&mut self, name: Name, idx_ex: Expr) -> Result<Value, PyError> {
-        let (list, idx) = self.list_and_idx(name, idx_ex)?;
+    // evaluates the start, stop and step of a slice, which have to be integers or None
+    fn eval_slice(
+        &mut self,
+        exprs: [Option<&Expr>; 3],
+        line: u64,
+        column: u64,
+    ) -> Result<Slice, PyError> {
+        let mut bounds = [None, None, None];
+        for (i, (bound, ex)) in bounds.iter_mut().zip(exprs).enumerate() {
+            if let Some(ex) = ex {
+                let val = self.eval_expr(ex)?;
+                *bound = slice_bound(val, i == 2, line, column)?;
+            }
+        }
+        Ok(Slice {
+            start: bounds[0],
+            stop: bounds[1],
+            step: bounds[2].unwrap_or(1),
+        })
+    }
 
-        Ok(list[idx as usize].clone())
+    // gets the list stored in a variable for assigning to one of its elements
+    fn list_var(&mut self, name: &Name) -> Result<ListRef, PyError> {
+        let val = self.env.get_var(name)?;
+        expect_list(val, name)
     }
 
-    // helper for list access and list element assign to check for list and idx validity
-    fn list_and_idx(&mut self, name: Name, idx_ex: Expr) -> Result<(Vec<Value>, i128), PyError> {
+    // helper for list element assign to check for list and idx validity
+    fn list_and_idx(&mut self, name: &Name, idx_ex: &Expr) -> Result<(ListRef, usize), PyError> {
         let idx_val = self.eval_expr(idx_ex)?;
-        let idx;
-        if let Value::Int(i) = idx_val {
-            idx = i;
-        } else {
+        let list = self.list_var(name)?;
+        let len = list.borrow().len();
+        let idx = check_list_idx(idx_val, len, name.line, name.column)?;
+        Ok((list, idx))
+    }
+}
+
+// which argument each parameter gets, as the index into the positional arguments followed by the keyword ones
+// the error is the message of the TypeError, also used by the linter for calls it can see
+pub fn match_arguments(
+    func: &str,
+    params: &[Name],
+    positional: usize,
+    keywords: &[&str],
+) -> Result<Vec<usize>, String> {
+    if positional > params.len() {
+        return Err(format!(
+            "TypeError: {func} takes {} positional arguments but {positional} were given",
+            params.len()
+        ));
+    }
+
+    let mut bound: Vec<Option<usize>> = (0..positional).map(Some).collect();
+    bound.resize(params.len(), None);
+    for (k, key) in keywords.iter().enumerate() {
+        let Some(i) = params.iter().position(|p| p.name == *key) else {
+            return Err(format!(
+                "TypeError: {func}() got an unexpected keyword argument '{key}'"
+            ));
+        };
+        if bound[i].is_some() {
+            return Err(format!(
+                "TypeError: {func}() got multiple values for argument '{key}'"
+            ));
+        }
+        bound[i] = Some(positional + k);
+    }
+
+    let missing: Vec<String> = params
+        .iter()
+        .zip(&bound)
+        .filter(|(_, b)| b.is_none())
+        .map(|(p, _)| format!("'{}'", p.name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "TypeError: {func}() missing {} required positional argument{}: {}",
+            missing.len(),
+            if missing.len() == 1 { "" } else { "s" },
+            missing.join(", ")
+        ));
+    }
+    Ok(bound.into_iter().flatten().collect())
+}
+
+// the value of a variable that is assigned an element or slice of, name is the variable
+fn expect_list(val: Value, name: &Name) -> Result<ListRef, PyError> {
+    match val {
+        Value::List(l) => Ok(l),
+        Value::String(_) => Err(PyError {
+            msg: "TypeError: 'str' object does not support item assignment".to_owned(),
+            line: name.line,
+            column: name.column,
+        }),
+        _ => Err(PyError {
+            msg: format!(
+                "TypeError: {} is not indexable, because it is not a list",
+                name.name
+            ),
+            line: name.line,
+            column: name.column,
+        }),
+    }
+}
+
+// list[idx] or string[idx]
+fn get_item(target: Value, idx_val: Value, loc: &Location) -> Result<Value, PyError> {
+    match target {
+        Value::List(list) => {
+            let list = list.borrow();
+            let idx = check_list_idx(idx_val, list.len(), loc.line, loc.column)?;
+            Ok(list[idx].clone())
+        }
+        Value::String(s) => {
+            let Value::Int(i) = idx_val else {
+                return Err(PyError {
+                    msg: "TypeError: string indices must be integers".to_owned(),
+                    line: loc.line,
+                    column: loc.column,
+                });
+            };
+            match normalize_index(i, s.chars().count()).and_then(|i| s.chars().nth(i)) {
+                Some(c) => Ok(Value::String(c.to_string())),
+                None => Err(PyError {
+                    msg: "IndexError: string index out of range".to_owned(),
+                    line: loc.line,
+                    column: loc.column,
+                }),
+            }
+        }
+        v => Err(PyError {
+            msg: format!("TypeError: '{}' object is not subscriptable", v.type_name()),
+            line: loc.line,
+            column: loc.column,
+        }),
+    }
+}
+
+// list[start:stop:step] or string[start:stop:step]
+fn get_slice(target: Value, slice: Slice, loc: &Location) -> Result<Value, PyError> {
+    match target {
+        Value::List(list) => {
+            let list = list.borrow();
+            Ok(Value::list(
+                slice.indices(list.len()).map(|i| list[i].clone()).collect(),
+            ))
+        }
+        Value::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            Ok(Value::String(
+                slice.indices(chars.len()).map(|i| chars[i]).collect(),
+            ))
+        }
+        v => Err(PyError {
+            msg: format!("TypeError: '{}' object is not subscriptable", v.type_name()),
+            line: loc.line,
+            column: loc.column,
+        }),
+    }
+}
+
+// a bound of a slice has to be an integer or None, the step can't be zero either
+fn slice_bound(val: Value, is_step: bool, line: u64, column: u64) -> Result<Option<i128>, PyError> {
+    let bound = match val {
+        Value::Int(n) => Some(n),
+        Value::Bool(b) => Some(b as i128),
+        Value::None => None,
+        _ => {
             return Err(PyError {
-                msg: "TypeError: List index must be an integer value".to_owned(),
-                line: name.line,
-                column: name.column,
-            });
+                msg: "TypeError: slice indices must be integers or None".to_owned(),
+                line,
+                column,
+            })
         }
+    };
+    if is_step && bound == Some(0) {
+        return Err(PyError {
+            msg: "ValueError: slice step cannot be zero".to_owned(),
+            line,
+            column,
+        });
+    }
+    Ok(bound)
+}
 
-        let list_val = self.env.get_var(name.clone())?;
-        let list;
-        if let Value::List(l) = list_val {
-            list = l;
+// the value of name in an imported module for from ... import name
+fn import_from(module: &Module, name: &Name) -> Result<Value, PyError> {
+    let val = module.vars.borrow().get(&name.name).cloned();
+    val.ok_or_else(|| PyError {
+        msg: format!(
+            "ImportError: cannot import name '{}' from '{}'",
+            name.name, module.name
+        ),
+        line: name.line,
+        column: name.column,
+    })
+}
+
+// name of a method like __enter__ that is called implicitly, located at the statement calling it
+fn special_method(name: &str, loc: &Location) -> Name {
+    Name {
+        name: name.to_owned(),
+        line: loc.line,
+        column: loc.column,
+        span: loc.span,
+    }
+}
+
+// membership test for in, elements of a list or substrings of a string
+fn contains(
+    container: &Value,
+    item: &Value,
+    op: &BiOp,
+    limits: &mut Limits,
+) -> Result<bool, PyError> {
+    let error = |msg| PyError {
+        msg,
+        line: op.line,
+        column: op.column,
+    };
+    match (container, item) {
+        (Value::List(l), _) => {
+            for v in l.borrow().iter() {
+                if v.equals(item, limits).map_err(error)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        (Value::String(s), Value::String(sub)) => Ok(s.contains(sub.as_str())),
+        // like in python this reads the lines up to the one found
+        (Value::File(f), _) => {
+            let mut f = f.borrow_mut();
+            loop {
+                let line = f.readline().map_err(error)?;
+                if line.is_empty() {
+                    return Ok(false);
+                }
+                if matches!(item, Value::String(s) if *s == line) {
+                    return Ok(true);
+                }
+            }
+        }
+        (Value::String(_), v) => Err(PyError {
+            msg: format!(
+                "TypeError: 'in <string>' requires string as left operand, not {}",
+                v.type_name()
+            ),
+            line: op.line,
+            column: op.column,
+        }),
+        (v, _) => Err(PyError {
+            msg: format!(
+                "TypeError: argument of type '{}' is not iterable",
+                v.type_name()
+            ),
+            line: op.line,
+            column: op.column,
+        }),
+    }
+}
+
+// slice with evaluated bounds, the step is never zero
+struct Slice {
+    start: Option<i128>,
+    stop: Option<i128>,
+    step: i128,
+}
+
+impl Slice {
+    // start and stop adjusted to a sequence of length len, like python's slice.indices()
+    fn bounds(&self, len: usize) -> (i128, i128) {
+        let len = len as i128;
+        // stepping backwards the first element is at len - 1 and -1 stops before the first one
+        let (lower, upper) = if self.step > 0 {
+            (0, len)
         } else {
-            return Err(PyError {
-                msg: format!(
-                    "TypeError: {} is not indexable, because it is not a list",
-                    name.name
-                ),
-                line: name.line,
-                column: name.column,
-            });
+            (-1, len - 1)
+        };
+        let adjust = |bound: Option<i128>, default: i128| match bound {
+            None => default,
+            Some(b) if b < 0 => (b + len).max(lower),
+            Some(b) => b.min(upper),
+        };
+        if self.step > 0 {
+            (adjust(self.start, lower), adjust(self.stop, upper))
+        } else {
+            (adjust(self.start, upper), adjust(self.stop, lower))
         }
+    }
 
-        if idx as usize >= list.len() {
+    // positions of the elements in the slice, in order
+    fn indices(&self, len: usize) -> impl Iterator<Item = usize> {
+        let (start, stop) = self.bounds(len);
+        let step = self.step;
+        // None once the next index doesn't fit into an i128, it would be past the end anyway
+        let mut i = Some(start);
+        std::iter::from_fn(move || {
+            let current = i?;
+            if (step > 0 && current < stop) || (step < 0 && current > stop) {
+                i = current.checked_add(step);
+                Some(current as usize)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+// replaces the elements of the slice, simple slices can change the length of the list, extended ones (step != 1) can't
+fn assign_slice(
+    list: &RefCell<Vec<Value>>,
+    slice: Slice,
+    val: Value,
+    line: u64,
+    column: u64,
+) -> Result<(), PyError> {
+    // copied first, because a list can be assigned to a slice of itself
+    let elems = match val.iterate() {
+        Some(elems) => elems.map_err(|msg| PyError { msg, line, column })?,
+        None => {
             return Err(PyError {
-                msg: "IndexError: Index out of bounds".to_owned(),
-                line: name.line,
-                column: name.column,
-            });
+                msg: "TypeError: can only assign an iterable".to_owned(),
+                line,
+                column,
+            })
         }
-        if idx < 0 {
-            return Err(PyError {
-                msg: "IndexError: Index below zero".to_owned(),
-                line: name.line,
-                column: name.column,
-            });
+    };
+    let mut list = list.borrow_mut();
+    if slice.step == 1 {
+        let (start, stop) = slice.bounds(list.len());
+        let stop = stop.max(start);
+        list.splice(start as usize..stop as usize, elems);
+        return Ok(());
+    }
+    let indices: Vec<usize> = slice.indices(list.len()).collect();
+    if indices.len() != elems.len() {
+        return Err(PyError {
+            msg: format!(
+                "ValueError: attempt to assign sequence of size {} to extended slice of size {}",
+                elems.len(),
+                indices.len()
+            ),
+            line,
+            column,
+        });
+    }
+    for (i, v) in indices.into_iter().zip(elems) {
+        list[i] = v;
+    }
+    Ok(())
+}
+
+// the list can have gotten shorter since the index was checked, while the value was evaluated
+fn set_item(
+    list: &RefCell<Vec<Value>>,
+    idx: usize,
+    val: Value,
+    line: u64,
+    column: u64,
+) -> Result<(), PyError> {
+    match list.borrow_mut().get_mut(idx) {
+        Some(elem) => {
+            *elem = val;
+            Ok(())
         }
+        None => Err(PyError {
+            msg: "IndexError: Index out of bounds".to_owned(),
+            line,
+            column,
+        }),
+    }
+}
 
-        Ok((list, idx))
+// checks that the index is an integer inside the list, negative ones count from the end
+fn check_list_idx(idx_val: Value, len: usize, line: u64, column: u64) -> Result<usize, PyError> {
+    let idx;
+    if let Value::Int(i) = idx_val {
+        idx = i;
+    } else {
+        return Err(PyError {
+            msg: "TypeError: List index must be an integer value".to_owned(),
+            line,
+            column,
+        });
+    }
+
+    normalize_index(idx, len).ok_or(PyError {
+        msg: "IndexError: Index out of bounds".to_owned(),
+        line,
+        column,
+    })
+}
+
+// turns a possibly negative index (counting from the end) into a valid position, None if out of range
+pub fn normalize_index(idx: i128, len: usize) -> Option<usize> {
+    let idx = if idx < 0 { idx + len as i128 } else { idx };
+    if idx < 0 || idx >= len as i128 {
+        None
+    } else {
+        Some(idx as usize)
     }
 }
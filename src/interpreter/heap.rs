@@ -0,0 +1,316 @@
+// every list, cell, function and module is made here and registered, so reference cycles between them can be found
+// reference counting frees everything else, the collector only has to break cycles, like python's gc module
+// it uses trial deletion: references between registered objects are subtracted from their reference counts,
+// objects with references left are used from outside (variables, the stack of the vm, ...) and are kept
+// with everything they reach, the rest is garbage and gets emptied, which frees it
+
+use std::{
+    collections::HashMap,
+    mem,
+    rc::{Rc, Weak},
+};
+
+use super::*;
+
+// registered objects that start a collection on their own, grows with the objects that survive
+const MIN_THRESHOLD: usize = 10_000;
+
+thread_local! {
+    static HEAP: RefCell<Heap> = const {
+        RefCell::new(Heap {
+            objects: Vec::new(),
+            threshold: MIN_THRESHOLD,
+            enabled: true,
+        })
+    };
+}
+
+struct Heap {
+    objects: Vec<Tracked>,
+    // a collection starts when this many objects are registered
+    threshold: usize,
+    // gc.disable() stops the automatic collections, gc.collect() still works
+    enabled: bool,
+}
+
+// the values that can be part of a cycle, functions can't change but hold their cells and module
+enum Object {
+    List(ListRef),
+    Cell(Cell),
+    Function(Rc<Function>),
+    Module(Rc<Module>),
+}
+
+enum Tracked {
+    List(Weak<RefCell<Vec<Value>>>),
+    Cell(Weak<RefCell<Option<Value>>>),
+    Function(Weak<Function>),
+    Module(Weak<Module>),
+}
+
+pub fn list(elems: Vec<Value>) -> ListRef {
+    let list = Rc::new(RefCell::new(elems));
+    track(Tracked::List(Rc::downgrade(&list)));
+    list
+}
+
+// a variable shared with nested functions, without a value yet
+pub fn cell() -> Cell {
+    let cell = Rc::new(RefCell::new(None));
+    track(Tracked::Cell(Rc::downgrade(&cell)));
+    cell
+}
+
+pub fn function(f: Function) -> Rc<Function> {
+    let f = Rc::new(f);
+    track(Tracked::Function(Rc::downgrade(&f)));
+    f
+}
+
+pub fn module(name: String, vars: HashMap<String, Value>) -> Rc<Module> {
+    let module = Rc::new(Module {
+        name,
+        vars: RefCell::new(vars),
+    });
+    track(Tracked::Module(Rc::downgrade(&module)));
+    module
+}
+
+fn track(object: Tracked) {
+    let full = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.objects.push(object);
+        heap.enabled && heap.objects.len() >= heap.threshold
+    });
+    if full {
+        collect();
+    }
+}
+
+pub fn set_enabled(enabled: bool) {
+    HEAP.with(|heap| heap.borrow_mut().enabled = enabled);
+}
+
+pub fn is_enabled() -> bool {
+    HEAP.with(|heap| heap.borrow().enabled)
+}
+
+// frees the objects only reachable through cycles, returns how many there were
+// safe to call at any time, objects that are borrowed mutably right now are kept with everything in them
+pub fn collect() -> usize {
+    // upgrading keeps every object alive during the collection, so each has one reference more
+    let objects: Vec<Object> = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.objects.retain(Tracked::is_alive);
+        heap.objects.iter().filter_map(Tracked::upgrade).collect()
+    });
+    let index: HashMap<usize, usize> = objects
+        .iter()
+        .enumerate()
+        .map(|(i, o)| (o.address(), i))
+        .collect();
+
+    let mut refs: Vec<usize> = objects.iter().map(|o| o.strong_count() - 1).collect();
+    // None for borrowed objects, the references in them aren't subtracted, so what they hold is kept too
+    let children: Vec<Option<Vec<usize>>> = objects.iter().map(|o| o.children(&index)).collect();
+    for &child in children.iter().flatten().flatten() {
+        refs[child] -= 1;
+    }
+
+    let mut reachable = vec![false; objects.len()];
+    let mut todo: Vec<usize> = (0..objects.len())
+        .filter(|&i| refs[i] > 0 || children[i].is_none())
+        .collect();
+    while let Some(i) = todo.pop() {
+        if !mem::replace(&mut reachable[i], true) {
+            todo.extend(children[i].iter().flatten());
+        }
+    }
+
+    // emptying the garbage breaks the cycles, the contents are only dropped at the end,
+    // when nothing of the heap is borrowed anymore
+    let mut contents = Vec::new();
+    let mut garbage = 0;
+    for (object, _) in objects.iter().zip(&reachable).filter(|(_, r)| !**r) {
+        garbage += 1;
+        match object {
+            Object::List(l) => {
+                if let Ok(mut l) = l.try_borrow_mut() {
+                    contents.append(&mut l);
+                }
+            }
+            Object::Cell(c) => {
+                if let Ok(mut c) = c.try_borrow_mut() {
+                    contents.extend(c.take());
+                }
+            }
+            Object::Module(m) => {
+                if let Ok(mut vars) = m.vars.try_borrow_mut() {
+                    contents.extend(mem::take(&mut *vars).into_values());
+                }
+            }
+            // only holds cells and a module, emptying those is enough
+            Object::Function(_) => (),
+        }
+    }
+    drop(contents);
+    drop(objects);
+
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.objects.retain(Tracked::is_alive);
+        heap.threshold = MIN_THRESHOLD.max(2 * heap.objects.len());
+    });
+    garbage
+}
+
+impl Tracked {
+    fn is_alive(&self) -> bool {
+        match self {
+            Tracked::List(w) => w.strong_count() > 0,
+            Tracked::Cell(w) => w.strong_count() > 0,
+            Tracked::Function(w) => w.strong_count() > 0,
+            Tracked::Module(w) => w.strong_count() > 0,
+        }
+    }
+
+    fn upgrade(&self) -> Option<Object> {
+        match self {
+            Tracked::List(w) => w.upgrade().map(Object::List),
+            Tracked::Cell(w) => w.upgrade().map(Object::Cell),
+            Tracked::Function(w) => w.upgrade().map(Object::Function),
+            Tracked::Module(w) => w.upgrade().map(Object::Module),
+        }
+    }
+}
+
+impl Object {
+    fn address(&self) -> usize {
+        match self {
+            Object::List(l) => address(l),
+            Object::Cell(c) => address(c),
+            Object::Function(f) => address(f),
+            Object::Module(m) => address(m),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Object::List(l) => Rc::strong_count(l),
+            Object::Cell(c) => Rc::strong_count(c),
+            Object::Function(f) => Rc::strong_count(f),
+            Object::Module(m) => Rc::strong_count(m),
+        }
+    }
+
+    // the registered objects this one holds a reference to, once for every reference
+    fn children(&self, index: &HashMap<usize, usize>) -> Option<Vec<usize>> {
+        let addresses: Vec<usize> = match self {
+            Object::List(l) => l
+                .try_borrow()
+                .ok()?
+                .iter()
+                .filter_map(value_address)
+                .collect(),
+            Object::Cell(c) => c
+                .try_borrow()
+                .ok()?
+                .iter()
+                .filter_map(value_address)
+                .collect(),
+            Object::Function(f) => f
+                .cells
+                .iter()
+                .map(address)
+                .chain([address(&f.globals)])
+                .collect(),
+            Object::Module(m) => m
+                .vars
+                .try_borrow()
+                .ok()?
+                .values()
+                .filter_map(value_address)
+                .collect(),
+        };
+        Some(
+            addresses
+                .iter()
+                .filter_map(|a| index.get(a).copied())
+                .collect(),
+        )
+    }
+}
+
+fn address<T>(rc: &Rc<T>) -> usize {
+    Rc::as_ptr(rc) as *const () as usize
+}
+
+// the address of the object a value refers to, if it can be registered
+fn value_address(v: &Value) -> Option<usize> {
+    match v {
+        Value::List(l) => Some(address(l)),
+        Value::Function(f) => Some(address(f)),
+        Value::Module(m) => Some(address(m)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // the heap is per thread and every test runs on its own, so only the objects of the test are registered
+    use super::*;
+
+    #[test]
+    fn collects_list_containing_itself() {
+        let l = list(Vec::new());
+        l.borrow_mut().push(Value::List(l.clone()));
+        let weak = Rc::downgrade(&l);
+        drop(l);
+
+        assert_eq!(collect(), 1);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn collects_closure_referring_to_itself() {
+        // def f(): return f, nested in another function, f is a cell that holds the function
+        let globals = module("m".to_owned(), HashMap::new());
+        let weak_m = Rc::downgrade(&globals);
+        let c = cell();
+        let name = Name {
+            name: "f".to_owned(),
+            line: 1,
+            column: 5,
+            span: Span::default(),
+        };
+        let f = function(Function {
+            name,
+            parameters: Vec::new(),
+            body: Body::Ast(
+                Rc::new(Vec::new()),
+                Rc::new(FunctionScope::new(&[], &[], None)),
+            ),
+            globals,
+            cells: vec![c.clone()],
+        });
+        *c.borrow_mut() = Some(Value::Function(f.clone()));
+        let weak_f = Rc::downgrade(&f);
+        let weak_c = Rc::downgrade(&c);
+        drop((f, c));
+
+        // the module only stays alive through the function, so it is garbage as well
+        assert_eq!(collect(), 3);
+        assert!(weak_f.upgrade().is_none());
+        assert!(weak_c.upgrade().is_none());
+        assert!(weak_m.upgrade().is_none());
+    }
+
+    #[test]
+    fn keeps_cycle_used_from_outside() {
+        let l = list(Vec::new());
+        l.borrow_mut().push(Value::List(l.clone()));
+
+        assert_eq!(collect(), 0);
+        assert_eq!(l.borrow().len(), 1);
+    }
+}
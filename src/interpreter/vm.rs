@@ -0,0 +1,358 @@
+// stack machine running the bytecode from compiler.rs
+// variables, functions and modules are the same as in the tree-walker, only the statements are run differently
+
+use super::*;
+use crate::compiler::Instr;
+
+// the values, variables and open with statements of a running piece of code
+struct Frame {
+    stack: Vec<Value>,
+    // variables of a function, see FunctionScope
+    slots: Vec<Option<Value>>,
+    cells: Vec<Cell>,
+    // context managers whose __exit__ still has to be called, with the location of their with statement
+    managers: Vec<(Value, Location)>,
+}
+
+impl Frame {
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("stack underflow")
+    }
+
+    // the top n values in the order they were pushed
+    fn pop_n(&mut self, n: usize) -> Vec<Value> {
+        let at = self.stack.len() - n;
+        self.stack.split_off(at)
+    }
+
+    // the three bounds pushed by a slice, already checked by SliceBound
+    fn pop_slice(&mut self) -> Slice {
+        let mut bounds = self.pop_n(3).into_iter().map(|b| match b {
+            Value::Int(n) => Some(n),
+            _ => None,
+        });
+        let mut next = || bounds.next().flatten();
+        let (start, stop, step) = (next(), next(), next());
+        Slice {
+            start,
+            stop,
+            step: step.unwrap_or(1),
+        }
+    }
+}
+
+impl Interpreter {
+    // like interpret_stmt, returns the location and value of a return statement
+    // args are the bound arguments of a function, free the cells it took from enclosing functions
+    pub(super) fn execute(
+        &mut self,
+        code: &Code,
+        args: Vec<Value>,
+        free: &[Cell],
+    ) -> Result<Option<(Location, Value)>, PyError> {
+        let own_cells = (0..code.own_cells).map(|_| heap::cell());
+        let mut frame = Frame {
+            stack: Vec::new(),
+            slots: vec![None; code.slots.len()],
+            cells: own_cells.chain(free.iter().cloned()).collect(),
+            managers: Vec::new(),
+        };
+        for (param, arg) in code.parameters.iter().zip(args) {
+            match param {
+                Access::Local(i) => frame.slots[*i] = Some(arg),
+                Access::Cell(i) => *frame.cells[*i].borrow_mut() = Some(arg),
+                Access::Global => unreachable!("parameters are always local"),
+            }
+        }
+        match self.execute_frame(code, &mut frame) {
+            Err(mut e) => {
+                // the with statements are left innermost first, an error from __exit__ replaces the one before
+                while let Some((manager, loc)) = frame.managers.pop() {
+                    if let Err(exit_e) = self.exit_with(manager, &loc) {
+                        e = exit_e;
+                    }
+                }
+                Err(e)
+            }
+            res => res,
+        }
+    }
+
+    fn execute_frame(
+        &mut self,
+        code: &Code,
+        frame: &mut Frame,
+    ) -> Result<Option<(Location, Value)>, PyError> {
+        let mut pc = 0;
+        while let Some(instr) = code.instructions.get(pc) {
+            let loc = &code.locations[pc];
+            let error = |msg: String| PyError {
+                msg,
+                line: loc.line,
+                column: loc.column,
+            };
+            // names are only needed for calls and errors
+            let name = |i: usize| Name {
+                name: code.names[i].clone(),
+                line: loc.line,
+                column: loc.column,
+                span: loc.span,
+            };
+            pc += 1;
+            self.limits.borrow_mut().step(loc.line, loc.column)?;
+
+            match instr {
+                Instr::Const(i) => frame.stack.push(code.constants[*i].clone()),
+                Instr::LoadGlobal(i) => {
+                    let var = &code.names[*i];
+                    let val = lookup_global(&self.env.globals, var)
+                        .ok_or_else(|| unbound(var, Access::Global, 0, loc.line, loc.column))?;
+                    frame.stack.push(val);
+                }
+                Instr::StoreGlobal(i) => {
+                    let val = frame.pop();
+                    let var = code.names[*i].clone();
+                    self.env.globals.vars.borrow_mut().insert(var, val);
+                }
+                Instr::LoadLocal(i) => {
+                    let val = frame.slots[*i].clone().ok_or_else(|| {
+                        let access = Access::Local(*i);
+                        unbound(&code.slots[*i], access, 0, loc.line, loc.column)
+                    })?;
+                    frame.stack.push(val);
+                }
+                Instr::StoreLocal(i) => frame.slots[*i] = Some(frame.pop()),
+                Instr::LoadCell(i) => {
+                    let val = frame.cells[*i].borrow().clone().ok_or_else(|| {
+                        let access = Access::Cell(*i);
+                        unbound(
+                            &code.cells[*i],
+                            access,
+                            code.own_cells,
+                            loc.line,
+                            loc.column,
+                        )
+                    })?;
+                    frame.stack.push(val);
+                }
+                Instr::StoreCell(i) => {
+                    let val = frame.pop();
+                    *frame.cells[*i].borrow_mut() = Some(val);
+                }
+                Instr::Pop => {
+                    frame.pop();
+                }
+                Instr::BuildList(n) => {
+                    let elems = frame.pop_n(*n);
+                    frame.stack.push(Value::list(elems));
+                }
+                Instr::BuildString(n) => {
+                    let mut res = String::new();
+                    for part in frame.pop_n(*n) {
+                        match part {
+                            Value::String(s) => res.push_str(&s),
+                            _ => unreachable!("f-string parts are always strings"),
+                        }
+                    }
+                    frame.stack.push(Value::String(res));
+                }
+                Instr::FormatValue(conv) => {
+                    let Value::String(spec) = frame.pop() else {
+                        unreachable!("format specs are always strings");
+                    };
+                    let val = frame.pop();
+                    let limits = &mut self.limits.borrow_mut();
+                    let val = convert(val, *conv, limits).map_err(error)?;
+                    let formatted = format_value(&val, &spec, limits).map_err(error)?;
+                    frame.stack.push(Value::String(formatted));
+                }
+                Instr::Unary(ty) => {
+                    let right = frame.pop();
+                    let op = UnOp {
+                        ty: *ty,
+                        line: loc.line,
+                        column: loc.column,
+                        span: loc.span,
+                    };
+                    frame.stack.push(Self::unary_op(op, right)?);
+                }
+                Instr::Binary(ty) => {
+                    let right = frame.pop();
+                    let left = frame.pop();
+                    let op = BiOp {
+                        ty: *ty,
+                        line: loc.line,
+                        column: loc.column,
+                        span: loc.span,
+                    };
+                    let limits = &mut self.limits.borrow_mut();
+                    frame.stack.push(Self::binary_op(left, op, right, limits)?);
+                }
+                Instr::Compare(ty, end) => {
+                    let right = frame.pop();
+                    let left = frame.pop();
+                    let op = BiOp {
+                        ty: *ty,
+                        line: loc.line,
+                        column: loc.column,
+                        span: loc.span,
+                    };
+                    let limits = &mut self.limits.borrow_mut();
+                    let res = Self::binary_op(left, op, right.clone(), limits)?;
+                    if res.to_bool() {
+                        frame.stack.push(right);
+                    } else {
+                        frame.stack.push(res);
+                        pc = *end;
+                    }
+                }
+                Instr::Jump(target) => pc = *target,
+                Instr::JumpIfFalse(target) => {
+                    if !frame.pop().to_bool() {
+                        pc = *target;
+                    }
+                }
+                Instr::Call {
+                    name: n,
+                    args,
+                    keywords,
+                } => {
+                    let kwargs = pop_kwargs(frame, &code.keywords[*keywords]);
+                    let args = frame.pop_n(*args);
+                    let callee = frame.pop();
+                    let res = self.call_value(callee, args, kwargs, &name(*n))?;
+                    frame.stack.push(res);
+                }
+                Instr::LoadMethod(n) => {
+                    let object = frame.pop();
+                    // functions of modules are called like methods
+                    let callee = match &object {
+                        Value::Module(_) => self.get_attribute(object.clone(), &name(*n))?,
+                        _ => Value::None,
+                    };
+                    frame.stack.push(object);
+                    frame.stack.push(callee);
+                }
+                Instr::CallMethod {
+                    name: n,
+                    args,
+                    keywords,
+                } => {
+                    let kwargs = pop_kwargs(frame, &code.keywords[*keywords]);
+                    let args = frame.pop_n(*args);
+                    let callee = frame.pop();
+                    let object = frame.pop();
+                    let res = if let Value::Module(_) = object {
+                        self.call_value(callee, args, kwargs, &name(*n))?
+                    } else {
+                        let args = Args {
+                            positional: args,
+                            keyword: kwargs,
+                        };
+                        methods::call_method(self, object, &name(*n), args)?
+                    };
+                    frame.stack.push(res);
+                }
+                Instr::GetAttr(n) => {
+                    let object = frame.pop();
+                    frame.stack.push(self.get_attribute(object, &name(*n))?);
+                }
+                Instr::GetItem => {
+                    let idx = frame.pop();
+                    let target = frame.pop();
+                    frame.stack.push(get_item(target, idx, loc)?);
+                }
+                Instr::SliceBound { step } => {
+                    let bound = slice_bound(frame.pop(), *step, loc.line, loc.column)?;
+                    frame.stack.push(bound.map_or(Value::None, Value::Int));
+                }
+                Instr::GetSlice => {
+                    let slice = frame.pop_slice();
+                    let target = frame.pop();
+                    frame.stack.push(get_slice(target, slice, loc)?);
+                }
+                Instr::ExpectList(n) => {
+                    let list = expect_list(frame.pop(), &name(*n))?;
+                    frame.stack.push(Value::List(list));
+                }
+                Instr::CheckIndex => {
+                    let list = frame.pop();
+                    let Value::List(l) = &list else {
+                        unreachable!("ExpectList always pushes a list");
+                    };
+                    let len = l.borrow().len();
+                    let idx = check_list_idx(frame.pop(), len, loc.line, loc.column)?;
+                    frame.stack.push(Value::Int(idx as i128));
+                    frame.stack.push(list);
+                }
+                Instr::SetItem => {
+                    let val = frame.pop();
+                    let (Value::List(list), Value::Int(idx)) = (frame.pop(), frame.pop()) else {
+                        unreachable!("CheckIndex always pushes a position and a list");
+                    };
+                    set_item(&list, idx as usize, val, loc.line, loc.column)?;
+                }
+                Instr::SetSlice => {
+                    let val = frame.pop();
+                    let Value::List(list) = frame.pop() else {
+                        unreachable!("ExpectList always pushes a list");
+                    };
+                    let slice = frame.pop_slice();
+                    assign_slice(&list, slice, val, loc.line, loc.column)?;
+                }
+                Instr::MakeFunction(i) => {
+                    let def = &code.functions[*i];
+                    let f = Function {
+                        name: def.name.clone(),
+                        parameters: def.parameters.clone(),
+                        body: Body::Code(def.code.clone()),
+                        globals: self.env.globals.clone(),
+                        cells: def.free.iter().map(|i| frame.cells[*i].clone()).collect(),
+                    };
+                    frame.stack.push(Value::Function(heap::function(f)));
+                }
+                Instr::Import(n) => {
+                    let module = self.import_module(&name(*n))?;
+                    frame.stack.push(Value::Module(module));
+                }
+                Instr::ImportFrom(n) => {
+                    let Some(Value::Module(module)) = frame.stack.last() else {
+                        unreachable!("ImportFrom always follows Import");
+                    };
+                    let val = import_from(module, &name(*n))?;
+                    frame.stack.push(val);
+                }
+                Instr::EnterWith => {
+                    let manager = frame.pop();
+                    let entered = self.enter_with(&manager, loc)?;
+                    frame.managers.push((manager, loc.clone()));
+                    frame.stack.push(entered);
+                }
+                Instr::ExitWith => {
+                    let (manager, loc) = frame.managers.pop().expect("ExitWith without EnterWith");
+                    self.exit_with(manager, &loc)?;
+                }
+                Instr::Return => {
+                    let val = frame.pop();
+                    while let Some((manager, loc)) = frame.managers.pop() {
+                        self.exit_with(manager, &loc)?;
+                    }
+                    return Ok(Some((loc.clone(), val)));
+                }
+                Instr::Raise(i) => {
+                    let Value::String(msg) = &code.constants[*i] else {
+                        unreachable!("error messages are strings");
+                    };
+                    return Err(error(msg.clone()));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+// the keyword arguments on top of the stack with their names
+fn pop_kwargs(frame: &mut Frame, names: &[String]) -> Vec<(String, Value)> {
+    let vals = frame.pop_n(names.len());
+    names.iter().cloned().zip(vals).collect()
+}
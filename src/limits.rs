@@ -0,0 +1,262 @@
+// limits that stop a script with an error before it can take down the interpreter
+// a host running untrusted code sets Quotas, the recursion limit is always there like in python
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
+use crate::common::{ast::Name, py_error::PyError};
+
+// size of the thread scripts run on, calls stop with a RecursionError before it is used up
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+// room left for the parts of a call that aren't checked, e.g. deeply nested expressions
+const STACK_RESERVE: usize = 16 * 1024 * 1024;
+
+// python's default for sys.getrecursionlimit()
+const DEFAULT_RECURSION_LIMIT: usize = 1000;
+// looking at the clock takes longer than a step, so it is only done every few steps
+const STEPS_PER_CLOCK_CHECK: u64 = 1024;
+
+// what a script may use, None is unlimited
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Quotas {
+    // a step is an instruction of the vm, or a statement or loop iteration of the tree-walker
+    pub steps: Option<u64>,
+    pub time: Option<Duration>,
+    // bytes allocated while the script runs and not freed yet
+    pub heap: Option<usize>,
+    // bytes written by print, input prompts and file writes
+    pub output: Option<usize>,
+}
+
+pub struct Limits {
+    quotas: Quotas,
+    steps: u64,
+    deadline: Option<Instant>,
+    output: usize,
+    // maximum number of nested frames, the script itself and imported modules count as well
+    pub recursion_limit: usize,
+    pub depth: usize,
+    // address of a variable near the start of the stack, to see how much of it is used
+    stack_start: usize,
+}
+
+impl Limits {
+    // has to be called on the thread the script runs on, the time and heap are counted from here
+    pub fn new(quotas: Quotas) -> Self {
+        HEAP_QUOTA.set(quotas.heap.map(|max| (ALLOCATED.get(), max)));
+        Limits {
+            quotas,
+            deadline: quotas.time.map(|t| Instant::now() + t),
+            ..Self::unlimited()
+        }
+    }
+
+    // without setting the heap quota of the thread, for evaluating constants while compiling,
+    // which can happen in the middle of a script when it imports a module
+    pub fn unlimited() -> Self {
+        let start = 0u8;
+        Limits {
+            quotas: Quotas::default(),
+            steps: 0,
+            deadline: None,
+            output: 0,
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+            depth: 1,
+            stack_start: stack_address(&start),
+        }
+    }
+
+    // counts a step at the given location and checks the step, time and heap quotas
+    pub fn step(&mut self, line: u64, column: u64) -> Result<(), PyError> {
+        let error = |msg: String| Err(PyError { msg, line, column });
+        self.steps += 1;
+        if let Some(max) = self.quotas.steps {
+            if self.steps > max {
+                return error(format!(
+                    "StepLimitError: the script took more than {max} steps"
+                ));
+            }
+        }
+        if let Some(deadline) = self.deadline {
+            if self.steps.is_multiple_of(STEPS_PER_CLOCK_CHECK) && Instant::now() >= deadline {
+                let secs = self.quotas.time.unwrap_or_default().as_secs_f64();
+                return error(format!(
+                    "TimeoutError: the script ran longer than {secs} seconds"
+                ));
+            }
+        }
+        check_heap(0).or_else(error)
+    }
+
+    // called before bytes are written, nothing is written if they don't fit anymore
+    pub fn output(&mut self, bytes: usize, name: &Name) -> Result<(), PyError> {
+        if let Some(max) = self.quotas.output {
+            if self.output + bytes > max {
+                return Err(PyError {
+                    msg: format!("OutputLimitError: the script wrote more than {max} bytes"),
+                    line: name.line,
+                    column: name.column,
+                });
+            }
+        }
+        self.output += bytes;
+        Ok(())
+    }
+
+    // called before a function body or module runs, name is the called name
+    pub fn enter(&mut self, name: &Name) -> Result<(), PyError> {
+        self.enter_nested("").map_err(|msg| PyError {
+            msg,
+            line: name.line,
+            column: name.column,
+        })
+    }
+
+    // called before the elements of a list are compared or printed, they can contain lists themselves,
+    // e.g. a list containing itself, counts like a call, context is added to the message
+    pub fn enter_nested(&mut self, context: &str) -> Result<(), String> {
+        // a high recursion limit must not overflow the real stack
+        let marker = 0u8;
+        let used = self.stack_start.abs_diff(stack_address(&marker));
+        if self.depth >= self.recursion_limit || used > STACK_SIZE - STACK_RESERVE {
+            return Err(format!(
+                "RecursionError: maximum recursion depth exceeded{context}"
+            ));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    // called after the frame is left, also when it is left with an error
+    pub fn leave(&mut self) {
+        self.depth -= 1;
+    }
+}
+
+fn stack_address(var: &u8) -> usize {
+    var as *const u8 as usize
+}
+
+// an empty string with room for bytes, for strings a script asks for at once, e.g. with the width
+// of a format spec, the heap quota is checked before the memory is taken, and running out of it
+// is an error instead of aborting the process
+pub fn string(bytes: usize) -> Result<String, String> {
+    check_heap(bytes)?;
+    let mut s = String::new();
+    s.try_reserve_exact(bytes)
+        .map_err(|_| "MemoryError: out of memory".to_owned())?;
+    Ok(s)
+}
+
+// checks that bytes more fit into the heap quota of the script running on this thread
+fn check_heap(bytes: usize) -> Result<(), String> {
+    let Some((start, max)) = HEAP_QUOTA.get() else {
+        return Ok(());
+    };
+    let used = usize::try_from(ALLOCATED.get() - start).unwrap_or(0);
+    if used.saturating_add(bytes) > max {
+        return Err(format!(
+            "MemoryError: the script used more than {max} bytes of memory"
+        ));
+    }
+    Ok(())
+}
+
+thread_local! {
+    // bytes allocated by this thread and not freed yet, so scripts running at the same time on
+    // other threads don't count, memory freed on another thread than it was taken on is
+    // subtracted there, which can make this negative
+    static ALLOCATED: Cell<isize> = const { Cell::new(0) };
+    // the heap quota of the script running on this thread, with ALLOCATED when it started
+    static HEAP_QUOTA: Cell<Option<(isize, usize)>> = const { Cell::new(None) };
+}
+
+// the system allocator, but keeping track of how much memory is in use
+struct CountingAlloc;
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+// adds to the bytes of this thread, the thread local has no destructor, so it can be used
+// while the thread is shutting down as well
+fn count(bytes: isize) {
+    ALLOCATED.set(ALLOCATED.get().wrapping_add(bytes));
+}
+
+// SAFETY: everything is passed on to the system allocator unchanged
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            count(layout.size() as isize);
+        }
+        ptr
+    }
+
+    // not inlined, dropping a deeply nested list goes through here once for every level,
+    // and the frames of the drop code would get bigger
+    #[inline(never)]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        count(-(layout.size() as isize));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            count(new_size as isize - layout.size() as isize);
+        }
+        new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{Arc, Barrier},
+        thread,
+    };
+
+    fn heap_quota(max: usize) -> Limits {
+        Limits::new(Quotas {
+            heap: Some(max),
+            ..Quotas::default()
+        })
+    }
+
+    #[test]
+    fn big_strings_are_checked_before_they_are_taken() {
+        let _limits = heap_quota(100_000);
+        assert!(string(1000).is_ok());
+        assert_eq!(
+            string(1_000_000_000_000).unwrap_err(),
+            "MemoryError: the script used more than 100000 bytes of memory"
+        );
+    }
+
+    #[test]
+    fn memory_of_other_threads_does_not_count() {
+        let mut limits = heap_quota(1_000_000);
+        let barrier = Arc::new(Barrier::new(2));
+        let other = {
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let memory = vec![1u8; 10_000_000];
+                barrier.wait();
+                barrier.wait();
+                memory.len()
+            })
+        };
+        barrier.wait();
+        assert!(limits.step(1, 1).is_ok());
+        barrier.wait();
+        other.join().unwrap();
+        let memory = vec![1u8; 10_000_000];
+        assert!(limits.step(1, 1).is_err());
+        drop(memory);
+    }
+}
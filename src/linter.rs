@@ -0,0 +1,339 @@
+// the checks of --check, they look at the syntax tree without running it
+// besides the names the resolver can't find, it reports variables read before they are assigned,
+// calls of known functions with the wrong arguments, returns outside of functions,
+// code after a return and variables of functions that are never used
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    builtins,
+    common::{ast::*, py_error::PyError},
+    interpreter,
+    resolver::{self, FunctionScope},
+};
+
+// all problems in the order they appear, the ones that don't stop the code from running start with Warning
+pub fn lint(stmts: &[Stmt]) -> Vec<PyError> {
+    let mut linter = Linter {
+        problems: resolver::undefined_names(stmts),
+        scopes: Vec::new(),
+    };
+    linter.scope(&[], stmts, false);
+    let mut problems = linter.problems;
+    problems.sort_by_key(|e| (e.line, e.column));
+    problems
+}
+
+// a def with its parameters
+type Def<'a> = (&'a Name, &'a [Name]);
+
+struct Linter<'a> {
+    problems: Vec<PyError>,
+    // the module and the functions around the checked code, innermost last
+    scopes: Vec<Scope<'a>>,
+}
+
+struct Scope<'a> {
+    // with the def and its parameters, if the variable is only assigned by one def
+    vars: HashMap<&'a str, Option<Def<'a>>>,
+    // the variables that can have a value at the checked code, because they are assigned before it,
+    // in a branch before it or anywhere in a loop around it
+    bound: HashSet<&'a str>,
+    in_function: bool,
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, msg: String, line: u64, column: u64) {
+        self.problems.push(PyError { msg, line, column });
+    }
+
+    // the module, or a function with its parameters
+    fn scope(&mut self, params: &'a [Name], body: &'a [Stmt], in_function: bool) {
+        let mut assigned: Vec<(&str, Option<Def>)> =
+            params.iter().map(|p| (p.name.as_str(), None)).collect();
+        bindings(body, &mut assigned);
+        let mut vars = HashMap::new();
+        for (name, def) in assigned {
+            // assigned more than once, it isn't known which function is called
+            vars.entry(name).and_modify(|d| *d = None).or_insert(def);
+        }
+        self.scopes.push(Scope {
+            vars,
+            bound: params.iter().map(|p| p.name.as_str()).collect(),
+            in_function,
+        });
+        self.block(body, in_function);
+        self.scopes.pop();
+        if in_function {
+            self.unused(params, body);
+        }
+    }
+
+    fn block(&mut self, stmts: &'a [Stmt], in_function: bool) {
+        let mut returned: Option<&Location> = None;
+        let mut reported = false;
+        for st in stmts {
+            if let Some(ret) = returned.filter(|_| !reported) {
+                let loc = stmt_location(st).unwrap_or_else(|| ret.clone());
+                self.report(
+                    "Warning: unreachable code after return".to_owned(),
+                    loc.line,
+                    loc.column,
+                );
+                reported = true;
+            }
+            if let Stmt::Return(l, _) = st {
+                returned = returned.or(Some(l));
+            }
+            self.statement(st, in_function);
+        }
+    }
+
+    fn statement(&mut self, stmt: &'a Stmt, in_function: bool) {
+        match stmt {
+            Stmt::Expr(e) => self.expr(e),
+            Stmt::AssignVar(n, e) => {
+                self.expr(e);
+                self.bind(&n.name);
+            }
+            Stmt::AssignList(n, i, e) => {
+                self.read(n);
+                self.expr(i);
+                self.expr(e);
+            }
+            Stmt::If(_, c, t, e) => {
+                self.expr(c);
+                self.block(t, in_function);
+                if let Some(e) = e {
+                    self.block(e, in_function);
+                }
+            }
+            Stmt::While(_, c, b) => {
+                self.expr(c);
+                // the body can read what it assigns in the iteration before
+                let mut assigned = Vec::new();
+                bindings(b, &mut assigned);
+                for (name, _) in assigned {
+                    self.bind(name);
+                }
+                self.block(b, in_function);
+            }
+            Stmt::With(_, e, alias, b) => {
+                self.expr(e);
+                if let Some(alias) = alias {
+                    self.bind(&alias.name);
+                }
+                self.block(b, in_function);
+            }
+            Stmt::FunDecl(_, n, p, b) => {
+                self.scope(p, b, true);
+                self.bind(&n.name);
+            }
+            Stmt::Return(l, e) => {
+                if !in_function {
+                    // the interpreters only see this when the return is reached
+                    self.report(
+                        "SyntaxError: return statement outside of function".to_owned(),
+                        l.line,
+                        l.column,
+                    );
+                }
+                if let Some(e) = e {
+                    self.expr(e);
+                }
+            }
+            Stmt::Import(_, imported) | Stmt::FromImport(_, _, imported) => {
+                for (name, alias) in imported {
+                    self.bind(&alias.as_ref().unwrap_or(name).name);
+                }
+            }
+        }
+    }
+
+    fn bind(&mut self, name: &'a str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.bound.insert(name);
+        }
+    }
+
+    // a variable of the innermost scope that can't have a value yet, the module falls back to the builtins
+    fn read(&mut self, name: &'a Name) {
+        let Some(scope) = self.scopes.last_mut() else {
+            return;
+        };
+        let var = name.name.as_str();
+        if !scope.vars.contains_key(var)
+            || scope.bound.contains(var)
+            || (!scope.in_function && builtins::lookup(var).is_some())
+        {
+            return;
+        }
+        let msg = if scope.in_function {
+            format!("UnboundLocalError: cannot access local variable '{var}' where it is not associated with a value")
+        } else {
+            format!("NameError: name {var} is not defined")
+        };
+        // once is enough, the first read already stops the code
+        scope.bound.insert(var);
+        self.report(msg, name.line, name.column);
+    }
+
+    fn expr(&mut self, expr: &'a Expr) {
+        match expr {
+            Expr::Unary(_, e) | Expr::Grouping(e, _) | Expr::Attribute(e, _) => self.expr(e),
+            Expr::Binary(e1, _, e2) | Expr::ListAccess(e1, e2, ..) => {
+                self.expr(e1);
+                self.expr(e2);
+            }
+            Expr::Compare(e, rest) => {
+                self.expr(e);
+                for (_, e) in rest {
+                    self.expr(e);
+                }
+            }
+            Expr::Literal(Lit::List(elems), _) => {
+                for e in elems {
+                    self.expr(e);
+                }
+            }
+            Expr::Literal(..) => (),
+            Expr::Variable(n) => self.read(n),
+            Expr::Call(n, args, keywords, _) => {
+                self.read(n);
+                self.call(n, args.len(), keywords);
+                self.args(args, keywords);
+            }
+            Expr::MethodCall(o, _, args, keywords, _) => {
+                self.expr(o);
+                self.args(args, keywords);
+            }
+            Expr::FString(parts, _) => self.fstring(parts),
+            Expr::Slice(start, stop, step, _) => {
+                for e in [start, stop, step].into_iter().flatten() {
+                    self.expr(e);
+                }
+            }
+        }
+    }
+
+    fn args(&mut self, args: &'a [Expr], keywords: &'a [(Name, Expr)]) {
+        for a in args {
+            self.expr(a);
+        }
+        for (_, e) in keywords {
+            self.expr(e);
+        }
+    }
+
+    fn fstring(&mut self, parts: &'a [FStringPart]) {
+        for part in parts {
+            if let FStringPart::Field(e, _, spec, _) = part {
+                self.expr(e);
+                self.fstring(spec);
+            }
+        }
+    }
+
+    // checks the arguments like a call of the function would, if the name can only be one def
+    fn call(&mut self, name: &Name, positional: usize, keywords: &[(Name, Expr)]) {
+        let Some((depth, def)) = self
+            .scopes
+            .iter()
+            .rposition(|s| s.vars.contains_key(name.name.as_str()))
+            .map(|i| (i, self.scopes[i].vars[name.name.as_str()]))
+        else {
+            return;
+        };
+        let Some((def, params)) = def else {
+            return;
+        };
+        // a call before the def in the same function or module is a read before the assignment,
+        // or calls the builtin the def replaces later
+        let innermost = depth + 1 == self.scopes.len();
+        if innermost && (name.line, name.column) < (def.line, def.column) {
+            return;
+        }
+        let keywords: Vec<&str> = keywords.iter().map(|(k, _)| k.name.as_str()).collect();
+        if let Err(msg) = interpreter::match_arguments(&name.name, params, positional, &keywords) {
+            self.report(msg, name.line, name.column);
+        }
+    }
+
+    // variables a function assigns but never reads, neither itself nor in nested functions
+    fn unused(&mut self, params: &[Name], body: &[Stmt]) {
+        let mut assigned = Vec::new();
+        assignments(body, &mut assigned);
+        let mut reads = Vec::new();
+        resolver::used_names(body, &mut reads, &mut Vec::new());
+        let scope = FunctionScope::new(params, body, None);
+        let captured = &scope.cells[..scope.own_cells];
+
+        let mut seen: Vec<&str> = params.iter().map(|p| p.name.as_str()).collect();
+        for var in assigned {
+            if seen.contains(&var.name.as_str()) {
+                continue;
+            }
+            seen.push(&var.name);
+            if !reads.iter().any(|r| r.name == var.name) && !captured.contains(&var.name) {
+                self.report(
+                    format!(
+                        "Warning: local variable '{}' is assigned to but never used",
+                        var.name
+                    ),
+                    var.line,
+                    var.column,
+                );
+            }
+        }
+    }
+}
+
+// every assignment of a name, with the def and its parameters if it is one, not looking into the bodies of functions
+fn bindings<'a>(stmts: &'a [Stmt], names: &mut Vec<(&'a str, Option<Def<'a>>)>) {
+    for st in stmts {
+        match st {
+            Stmt::AssignVar(n, _) => names.push((&n.name, None)),
+            Stmt::FunDecl(_, n, p, _) => names.push((&n.name, Some((n, p)))),
+            Stmt::If(_, _, t, e) => {
+                bindings(t, names);
+                if let Some(e) = e {
+                    bindings(e, names);
+                }
+            }
+            Stmt::While(_, _, b) => bindings(b, names),
+            Stmt::With(_, _, alias, b) => {
+                if let Some(alias) = alias {
+                    names.push((&alias.name, None));
+                }
+                bindings(b, names);
+            }
+            Stmt::Import(_, imported) | Stmt::FromImport(_, _, imported) => {
+                for (name, alias) in imported {
+                    names.push((&alias.as_ref().unwrap_or(name).name, None));
+                }
+            }
+            Stmt::Expr(_) | Stmt::AssignList(..) | Stmt::Return(..) => (),
+        }
+    }
+}
+
+// the variables given a value by = or with ... as, in order, not looking into the bodies of functions
+fn assignments<'a>(stmts: &'a [Stmt], names: &mut Vec<&'a Name>) {
+    for st in stmts {
+        match st {
+            Stmt::AssignVar(n, _) => names.push(n),
+            Stmt::If(_, _, t, e) => {
+                assignments(t, names);
+                if let Some(e) = e {
+                    assignments(e, names);
+                }
+            }
+            Stmt::While(_, _, b) => assignments(b, names),
+            Stmt::With(_, _, alias, b) => {
+                names.extend(alias);
+                assignments(b, names);
+            }
+            _ => (),
+        }
+    }
+}
@@ -1,34 +1,133 @@
+mod builtins;
 mod common;
+mod compiler;
+mod cst;
+mod file;
+mod format;
+mod formatter;
 mod interpreter;
+mod limits;
+mod linter;
+mod methods;
+mod modules;
+mod native;
+mod optimizer;
 mod parser;
+mod resolver;
 mod scanner;
+mod streams;
 
 use std::{
-    cmp::Ordering,
     env,
-    fs::read_to_string,
+    fs::{self, read_to_string},
     io::{self, Write},
+    panic,
+    path::Path,
+    process,
+    str::FromStr,
+    thread,
+    time::Duration,
 };
 
+use interpreter::{Engine, Options};
+use limits::Quotas;
+
+// the linter, formatter and other passes over the syntax tree recurse into long chains of operators,
+// so they get a bigger stack than the main thread as well, scripts get their own in interpreter::interpret()
 fn main() -> Result<(), io::Error> {
-    let args: Vec<_> = env::args().collect();
-    match args.len().cmp(&2) {
-        // no arguments starts REPL
-        Ordering::Less => repl()?,
-        // one argument is assumed to be file path
-        Ordering::Equal => {
-            let path = args.get(1).unwrap();
-            let code = read_to_string(path)?;
-            run(code);
+    thread::Builder::new()
+        .stack_size(limits::STACK_SIZE)
+        .spawn(start)?
+        .join()
+        // the panic message was already printed by the thread
+        .unwrap_or_else(|e| panic::resume_unwind(e))
+}
+
+fn start() -> Result<(), io::Error> {
+    let mut args: Vec<_> = env::args().skip(1).collect();
+    // fmt [paths...] formats the files instead of running one
+    if args.first().is_some_and(|a| a == "fmt") {
+        let mut code = 0;
+        for path in &args[1..] {
+            code = code.max(format_file(path)?);
         }
-        Ordering::Greater => println!("Usage: cargo run [path]"),
+        process::exit(code);
+    }
+    // options come before the file path
+    let mut options = Options {
+        engine: Engine::Bytecode,
+        optimize: true,
+        quotas: Quotas::default(),
+    };
+    let mut check = false;
+    let mut print_cst = false;
+    while args.first().is_some_and(|a| a.starts_with("--")) {
+        let opt = args.remove(0);
+        let quotas = &mut options.quotas;
+        match opt.as_str() {
+            // the old interpreter, to compare its results with the vm
+            "--tree-walker" => options.engine = Engine::TreeWalker,
+            // runs the syntax tree as it was parsed, to compare the results with the optimized one
+            "--no-optimize" => options.optimize = false,
+            // reports problems of the script without running it
+            "--check" => check = true,
+            // prints the concrete syntax tree of the script instead of running it
+            "--cst" => print_cst = true,
+            // limits for running untrusted scripts
+            "--max-steps" => quotas.steps = Some(option_value(&mut args, &opt)),
+            "--timeout" => {
+                let secs: f64 = option_value(&mut args, &opt);
+                let time = Duration::try_from_secs_f64(secs).unwrap_or_else(|_| {
+                    eprintln!("invalid value {secs} for {opt}");
+                    process::exit(2);
+                });
+                quotas.time = Some(time);
+            }
+            "--max-heap" => quotas.heap = Some(option_value(&mut args, &opt)),
+            "--max-output" => quotas.output = Some(option_value(&mut args, &opt)),
+            opt => {
+                eprintln!("unknown option {opt}");
+                process::exit(2);
+            }
+        }
+    }
+    // no arguments starts REPL
+    if args.is_empty() {
+        return repl(options);
+    }
+    // the first argument is assumed to be the file path, the others are passed to the script
+    let path = &args[0];
+    let code = read_to_string(path)?;
+    if check {
+        process::exit(lint(code));
+    }
+    if print_cst {
+        process::exit(concrete_tree(code));
+    }
+    // imports are relative to the script
+    let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+    let code = run(code, dir, args.clone(), options);
+    if code != 0 {
+        process::exit(code);
     }
-
     Ok(())
 }
 
+// the argument after an option that needs a value, e.g. --max-steps 1000
+fn option_value<T: FromStr>(args: &mut Vec<String>, opt: &str) -> T {
+    if args.is_empty() {
+        eprintln!("missing value for {opt}");
+        process::exit(2);
+    }
+    let value = args.remove(0);
+    value.parse().unwrap_or_else(|_| {
+        eprintln!("invalid value {value} for {opt}");
+        process::exit(2);
+    })
+}
+
 // TODO: what about multi-line e.g. if, while, def?
-fn repl() -> Result<(), io::Error> {
+fn repl(options: Options) -> Result<(), io::Error> {
     let mut line = String::new();
     loop {
         line.clear();
@@ -40,30 +139,67 @@ fn repl() -> Result<(), io::Error> {
             Ok(_) => (),
             Err(e) => return Err(e),
         };
-        run(line.clone());
+        run(line.clone(), Path::new(""), vec![String::new()], options);
     }
     Ok(())
 }
 
-fn run(code: String) {
-    // stop running if there was an error
-    let tokens;
-    if let Some(t) = scanner::scan(code) {
-        tokens = t;
-    } else {
-        return;
+// rewrites the file in the style of formatter.rs, returns the exit code
+fn format_file(path: &str) -> Result<i32, io::Error> {
+    let code = read_to_string(path)?;
+    let Some((tokens, trivia)) = scanner::scan_with_trivia(code.clone()) else {
+        return Ok(1);
+    };
+    let Some(stmts) = parser::parse(tokens) else {
+        return Ok(1);
+    };
+    let formatted = formatter::format(&code, &stmts, trivia);
+    // a bug in the formatter must not break the file
+    if scanner::scan(formatted.clone())
+        .and_then(parser::parse)
+        .is_none()
+    {
+        eprintln!("formatting {path} gave code that doesn't parse, the file is left unchanged");
+        return Ok(1);
+    }
+    if formatted != code {
+        fs::write(path, formatted)?;
     }
-    // for t in &tokens {
-    //     println!("{:?}, {}, {}", t.token_type, t.line, t.column);
-    // }
+    Ok(0)
+}
 
-    let stmts;
-    if let Some(s) = parser::parse(tokens) {
-        stmts = s;
-    } else {
-        return;
+// prints what the linter finds, returns the exit code, 1 if it found anything
+fn lint(code: String) -> i32 {
+    let Some(tokens) = scanner::scan(code) else {
+        return 1;
+    };
+    let Some(stmts) = parser::parse(tokens) else {
+        return 1;
+    };
+    let problems = linter::lint(&stmts);
+    for p in &problems {
+        println!("{p}");
     }
-    // println!("\n{stmts:?}\n");
+    i32::from(!problems.is_empty())
+}
+
+// prints the tree of cst.rs, returns the exit code
+fn concrete_tree(code: String) -> i32 {
+    let Some(tokens) = scanner::scan(code.clone()) else {
+        return 1;
+    };
+    let Some(stmts) = parser::parse(tokens.clone()) else {
+        return 1;
+    };
+    let tree = cst::build(&code, &tokens, &stmts);
+    // the leaves have to give back the code
+    debug_assert_eq!(tree.to_string(), code);
+    print!("{}", tree.tree());
+    0
+}
 
-    interpreter::interpret(stmts);
+// returns the exit code
+fn run(code: String, dir: &Path, argv: Vec<String>, options: Options) -> i32 {
+    let streams = streams::Streams::standard();
+    interpreter::interpret(code, streams, dir, argv, options)
 }
@@ -0,0 +1,866 @@
+// methods of builtin types, e.g. "a,b".split(",")
+// every type has a table mapping method names to their native implementation
+
+use std::cmp::Ordering;
+
+use crate::{
+    builtins::{check_arity, error, Args},
+    common::{ast::Name, py_error::PyError},
+    format::format_value,
+    interpreter::{normalize_index, FileRef, Interpreter, ListRef, Value},
+    limits::Limits,
+};
+
+// format() needs the limits of the interpreter for printing lists
+type StrMethod = fn(&mut Interpreter, &str, Vec<Value>, &Name) -> Result<Value, PyError>;
+// list methods change the list in place, sort() needs the interpreter to call the key function,
+// comparing elements its limits
+type ListMethod = fn(&mut Interpreter, &ListRef, Args, &Name) -> Result<Value, PyError>;
+type FileMethod = fn(&FileRef, Vec<Value>, &Name) -> Result<Value, PyError>;
+
+const STR_METHODS: &[(&str, StrMethod)] = &[
+    ("upper", str_upper),
+    ("lower", str_lower),
+    ("strip", str_strip),
+    ("lstrip", str_lstrip),
+    ("rstrip", str_rstrip),
+    ("split", str_split),
+    ("join", str_join),
+    ("replace", str_replace),
+    ("find", str_find),
+    ("startswith", str_startswith),
+    ("endswith", str_endswith),
+    ("isdigit", str_isdigit),
+    ("format", str_format),
+];
+
+const LIST_METHODS: &[(&str, ListMethod)] = &[
+    ("append", list_append),
+    ("pop", list_pop),
+    ("insert", list_insert),
+    ("remove", list_remove),
+    ("extend", list_extend),
+    ("sort", list_sort),
+    ("reverse", list_reverse),
+    ("index", list_index),
+    ("count", list_count),
+];
+
+// __enter__ and __exit__ make files usable in with statements
+const FILE_METHODS: &[(&str, FileMethod)] = &[
+    ("read", file_read),
+    ("readline", file_readline),
+    ("readlines", file_readlines),
+    ("write", file_write),
+    ("flush", file_flush),
+    ("close", file_close),
+    ("__enter__", file_enter),
+    ("__exit__", file_exit),
+];
+
+pub fn has_method(object: &Value, name: &str) -> bool {
+    match object {
+        Value::String(_) => STR_METHODS.iter().any(|(n, _)| *n == name),
+        Value::List(_) => LIST_METHODS.iter().any(|(n, _)| *n == name),
+        Value::File(_) => FILE_METHODS.iter().any(|(n, _)| *n == name),
+        _ => false,
+    }
+}
+
+pub fn call_method(
+    int: &mut Interpreter,
+    object: Value,
+    name: &Name,
+    args: Args,
+) -> Result<Value, PyError> {
+    match &object {
+        Value::String(s) => {
+            if let Some((_, method)) = STR_METHODS.iter().find(|(n, _)| *n == name.name) {
+                let args = args.positional_only(&format!("str.{}", name.name), name)?;
+                return method(int, s, args, name);
+            }
+        }
+        Value::List(l) => {
+            if let Some((_, method)) = LIST_METHODS.iter().find(|(n, _)| *n == name.name) {
+                return method(int, l, args, name);
+            }
+        }
+        Value::File(f) => {
+            if let Some((_, method)) = FILE_METHODS.iter().find(|(n, _)| *n == name.name) {
+                let args = args.positional_only(&name.name, name)?;
+                // written text counts towards the output quota like print
+                if let ("write", Some(Value::String(s))) = (name.name.as_str(), args.first()) {
+                    int.limits.borrow_mut().output(s.len(), name)?;
+                }
+                return method(f, args, name);
+            }
+        }
+        _ => (),
+    }
+    Err(error(
+        format!(
+            "AttributeError: '{}' object has no attribute '{}'",
+            object.type_name(),
+            name.name
+        ),
+        name,
+    ))
+}
+
+/////////////
+// helpers //
+/////////////
+
+fn expect_str<'a>(val: &'a Value, func: &str, name: &Name) -> Result<&'a str, PyError> {
+    match val {
+        Value::String(s) => Ok(s),
+        v => Err(error(
+            format!(
+                "TypeError: {func}() argument must be str, not {}",
+                v.type_name()
+            ),
+            name,
+        )),
+    }
+}
+
+fn expect_int(val: &Value, func: &str, name: &Name) -> Result<i128, PyError> {
+    match val {
+        Value::Int(n) => Ok(*n),
+        Value::Bool(b) => Ok(*b as i128),
+        v => Err(error(
+            format!(
+                "TypeError: {func}() argument must be int, not {}",
+                v.type_name()
+            ),
+            name,
+        )),
+    }
+}
+
+// clamps a start or end index of a range into 0..=len, negative ones count from the end
+fn clamp_index(idx: i128, len: usize) -> usize {
+    if idx < 0 {
+        (idx + len as i128).max(0) as usize
+    } else {
+        usize::try_from(idx).map_or(len, |i| i.min(len))
+    }
+}
+
+////////////////////
+// string methods //
+////////////////////
+
+fn str_upper(
+    _: &mut Interpreter,
+    s: &str,
+    args: Vec<Value>,
+    name: &Name,
+) -> Result<Value, PyError> {
+    check_arity("str.upper", name, &args, 0, 0)?;
+    Ok(Value::String(s.to_uppercase()))
+}
+
+fn str_lower(
+    _: &mut Interpreter,
+    s: &str,
+    args: Vec<Value>,
+    name: &Name,
+) -> Result<Value, PyError> {
+    check_arity("str.lower", name, &args, 0, 0)?;
+    Ok(Value::String(s.to_lowercase()))
+}
+
+// the chars to strip, whitespace if not given or None
+fn strip_chars(func: &str, args: &[Value], name: &Name) -> Result<Option<Vec<char>>, PyError> {
+    check_arity(func, name, args, 0, 1)?;
+    match args.first() {
+        None | Some(Value::None) => Ok(None),
+        Some(Value::String(c)) => Ok(Some(c.chars().collect())),
+        Some(v) => Err(error(
+            format!(
+                "TypeError: {func} arg must be None or str, not {}",
+                v.type_name()
+            ),
+            name,
+        )),
+    }
+}
+
+fn str_strip(
+    _: &mut Interpreter,
+    s: &str,
+    args: Vec<Value>,
+    name: &Name,
+) -> Result<Value, PyError> {
+    Ok(Value::String(
+        match strip_chars("str.strip", &args, name)? {
+            Some(chars) => s.trim_matches(chars.as_slice()),
+            None => s.trim(),
+        }
+        .to_owned(),
+    ))
+}
+
+fn str_lstrip(
+    _: &mut Interpreter,
+    s: &str,
+    args: Vec<Value>,
+    name: &Name,
+) -> Result<Value, PyError> {
+    Ok(Value::String(
+        match strip_chars("str.lstrip", &args, name)? {
+            Some(chars) => s.trim_start_matches(chars.as_slice()),
+            None => s.trim_start(),
+        }
+        .to_owned(),
+    ))
+}
+
+fn str_rstrip(
+    _: &mut Interpreter,
+    s: &str,
+    args: Vec<Value>,
+    name: &Name,
+) -> Result<Value, PyError> {
+    Ok(Value::String(
+        match strip_chars("str.rstrip", &args, name)? {
+            Some(chars) => s.trim_end_matches(chars.as_slice()),
+            None => s.trim_end(),
+        }
+        .to_owned(),
+    ))
+}
+
+// split(sep=None, maxsplit=-1), without sep it splits at runs of whitespace and drops empty parts
+fn str_split(
+    _: &mut Interpreter,
+    s: &str,
+    args: Vec<Value>,
+    name: &Name,
+) -> Result<Value, PyError> {
+    check_arity("str.split", name, &args, 0, 2)?;
+    let sep = match args.first() {
+        None | Some(Value::None) => None,
+        Some(v) => Some(expect_str(v, "str.split", name)?),
+    };
+    let maxsplit = match args.get(1) {
+        Some(v) => expect_int(v, "str.split", name)?,
+        None => -1,
+    };
+    // negative maxsplit means no limit, one that doesn't fit into a usize can't be reached either
+    let limit = usize::try_from(maxsplit).map_or(usize::MAX, |m| m.saturating_add(1));
+
+    let parts: Vec<String> = match sep {
+        Some("") => return Err(error("ValueError: empty separator".to_owned(), name)),
+        Some(sep) => s.splitn(limit, sep).map(|p| p.to_owned()).collect(),
+        None => {
+            let mut parts = Vec::new();
+            let mut rest = s.trim_start();
+            while !rest.is_empty() {
+                if parts.len() + 1 == limit {
+                    parts.push(rest.to_owned());
+                    break;
+                }
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                parts.push(rest[..end].to_owned());
+                rest = rest[end..].trim_start();
+            }
+            parts
+        }
+    };
+    Ok(Value::list(parts.into_iter().map(Value::String).collect()))
+}
+
+fn str_join(_: &mut Interpreter, s: &str, args: Vec<Value>, name: &Name) -> Result<Value, PyError> {
+    check_arity("str.join", name, &args, 1, 1)?;
+    let items: Vec<String> = match &args[0] {
+        Value::List(l) => {
+            let mut items = Vec::new();
+            for (i, v) in l.borrow().iter().enumerate() {
+                match v {
+                    Value::String(item) => items.push(item.clone()),
+                    v => {
+                        return Err(error(
+                            format!(
+                                "TypeError: sequence item {i}: expected str instance, {} found",
+                                v.type_name()
+                            ),
+                            name,
+                        ))
+                    }
+                }
+            }
+            items
+        }
+        // strings are joined char by char
+        Value::String(other) => other.chars().map(|c| c.to_string()).collect(),
+        v => {
+            return Err(error(
+                format!(
+                    "TypeError: can only join an iterable, not {}",
+                    v.type_name()
+                ),
+                name,
+            ))
+        }
+    };
+    Ok(Value::String(items.join(s)))
+}
+
+// replace(old, new, count=-1)
+fn str_replace(
+    _: &mut Interpreter,
+    s: &str,
+    args: Vec<Value>,
+    name: &Name,
+) -> Result<Value, PyError> {
+    check_arity("str.replace", name, &args, 2, 3)?;
+    let old = expect_str(&args[0], "str.replace", name)?;
+    let new = expect_str(&args[1], "str.replace", name)?;
+    let count = match args.get(2) {
+        Some(v) => expect_int(v, "str.replace", name)?,
+        None => -1,
+    };
+    // negative count means all of them, like one too big for a usize
+    let res = match usize::try_from(count) {
+        Ok(count) => s.replacen(old, new, count),
+        Err(_) => s.replace(old, new),
+    };
+    Ok(Value::String(res))
+}
+
+// find(sub, start=None, end=None), returns the char index of the first occurrence or -1
+fn str_find(_: &mut Interpreter, s: &str, args: Vec<Value>, name: &Name) -> Result<Value, PyError> {
+    check_arity("str.find", name, &args, 1, 3)?;
+    let sub = expect_str(&args[0], "str.find", name)?;
+    let chars: Vec<char> = s.chars().collect();
+    let bound = |i: usize, default: usize| match args.get(i) {
+        None | Some(Value::None) => Ok(default),
+        Some(v) => Ok(clamp_index(expect_int(v, "str.find", name)?, chars.len())),
+    };
+    let start = bound(1, 0)?;
+    let end = bound(2, chars.len())?;
+    if start > end {
+        return Ok(Value::Int(-1));
+    }
+    let haystack: String = chars[start..end].iter().collect();
+    match haystack.find(sub) {
+        // byte offset has to be turned into a char offset
+        Some(b) => Ok(Value::Int((start + haystack[..b].chars().count()) as i128)),
+        None => Ok(Value::Int(-1)),
+    }
+}
+
+fn str_startswith(
+    _: &mut Interpreter,
+    s: &str,
+    args: Vec<Value>,
+    name: &Name,
+) -> Result<Value, PyError> {
+    check_arity("str.startswith", name, &args, 1, 1)?;
+    let prefix = expect_str(&args[0], "str.startswith", name)?;
+    Ok(Value::Bool(s.starts_with(prefix)))
+}
+
+fn str_endswith(
+    _: &mut Interpreter,
+    s: &str,
+    args: Vec<Value>,
+    name: &Name,
+) -> Result<Value, PyError> {
+    check_arity("str.endswith", name, &args, 1, 1)?;
+    let suffix = expect_str(&args[0], "str.endswith", name)?;
+    Ok(Value::Bool(s.ends_with(suffix)))
+}
+
+fn str_isdigit(
+    _: &mut Interpreter,
+    s: &str,
+    args: Vec<Value>,
+    name: &Name,
+) -> Result<Value, PyError> {
+    check_arity("str.isdigit", name, &args, 0, 0)?;
+    Ok(Value::Bool(
+        !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()),
+    ))
+}
+
+// "{} and {0!r:>5}".format(a), fields work like in f-strings, but refer to the arguments
+fn str_format(
+    int: &mut Interpreter,
+    s: &str,
+    args: Vec<Value>,
+    name: &Name,
+) -> Result<Value, PyError> {
+    // number of the next automatically numbered field, None after a manually numbered one
+    let mut auto_idx = Some(0);
+    Ok(Value::String(format_fields(
+        s,
+        &args,
+        &mut auto_idx,
+        name,
+        &mut int.limits.borrow_mut(),
+    )?))
+}
+
+fn format_fields(
+    s: &str,
+    args: &[Value],
+    auto_idx: &mut Option<usize>,
+    name: &Name,
+    limits: &mut Limits,
+) -> Result<String, PyError> {
+    let mut res = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' if chars.peek() == Some(&c) => {
+                chars.next();
+                res.push(c);
+            }
+            '}' => {
+                return Err(error(
+                    "ValueError: Single '}' encountered in format string".to_owned(),
+                    name,
+                ))
+            }
+            '{' => {
+                // collect the field up to the matching }, the spec can contain fields itself
+                let mut field = String::new();
+                let mut depth = 1;
+                loop {
+                    let Some(c) = chars.next() else {
+                        return Err(error(
+                            "ValueError: expected '}' before end of string".to_owned(),
+                            name,
+                        ));
+                    };
+                    match c {
+                        '{' => depth += 1,
+                        '}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => (),
+                    }
+                    field.push(c);
+                }
+                res.push_str(&format_field(&field, args, auto_idx, name, limits)?);
+            }
+            c => res.push(c),
+        }
+    }
+    Ok(res)
+}
+
+// formats a single field of the form index!conversion:spec
+fn format_field(
+    field: &str,
+    args: &[Value],
+    auto_idx: &mut Option<usize>,
+    name: &Name,
+    limits: &mut Limits,
+) -> Result<String, PyError> {
+    let (head, spec) = field.split_once(':').unwrap_or((field, ""));
+    let (index, conversion) = match head.split_once('!') {
+        Some((i, c)) => (i, Some(c)),
+        None => (head, None),
+    };
+
+    let idx = if index.is_empty() {
+        match *auto_idx {
+            Some(i) => {
+                *auto_idx = Some(i + 1);
+                i
+            }
+            None => {
+                return Err(error(
+                    "ValueError: cannot switch from manual field specification to automatic field numbering".to_owned(),
+                    name,
+                ))
+            }
+        }
+    } else {
+        if auto_idx.is_some_and(|i| i > 0) {
+            return Err(error(
+                "ValueError: cannot switch from automatic field numbering to manual field specification".to_owned(),
+                name,
+            ));
+        }
+        *auto_idx = None;
+        index
+            .parse::<usize>()
+            .map_err(|_| error(format!("KeyError: '{index}'"), name))?
+    };
+    let val = args.get(idx).ok_or(error(
+        format!("IndexError: Replacement index {idx} out of range for positional args tuple"),
+        name,
+    ))?;
+
+    let val = match conversion {
+        None => val.clone(),
+        Some("s") => Value::String(val.str(limits).map_err(|msg| error(msg, name))?),
+        Some("r") => Value::String(val.repr(limits).map_err(|msg| error(msg, name))?),
+        Some("a") => Value::String(val.ascii(limits).map_err(|msg| error(msg, name))?),
+        Some(c) => {
+            return Err(error(
+                format!("ValueError: Unknown conversion specifier {c}"),
+                name,
+            ))
+        }
+    };
+    let spec = format_fields(spec, args, auto_idx, name, limits)?;
+    format_value(&val, &spec, limits).map_err(|msg| error(msg, name))
+}
+
+//////////////////
+// list methods //
+//////////////////
+
+// positional arguments of a list method, checking their number
+fn list_args(
+    args: Args,
+    func: &str,
+    name: &Name,
+    min: usize,
+    max: usize,
+) -> Result<Vec<Value>, PyError> {
+    let args = args.positional_only(func, name)?;
+    check_arity(func, name, &args, min, max)?;
+    Ok(args)
+}
+
+fn list_append(
+    _: &mut Interpreter,
+    list: &ListRef,
+    args: Args,
+    name: &Name,
+) -> Result<Value, PyError> {
+    let mut args = list_args(args, "list.append", name, 1, 1)?;
+    list.borrow_mut().push(args.remove(0));
+    Ok(Value::None)
+}
+
+fn list_pop(
+    _: &mut Interpreter,
+    list: &ListRef,
+    args: Args,
+    name: &Name,
+) -> Result<Value, PyError> {
+    let args = list_args(args, "list.pop", name, 0, 1)?;
+    let mut list = list.borrow_mut();
+    if list.is_empty() {
+        return Err(error("IndexError: pop from empty list".to_owned(), name));
+    }
+    let idx = match args.first() {
+        Some(v) => expect_int(v, "list.pop", name)?,
+        None => -1,
+    };
+    match normalize_index(idx, list.len()) {
+        Some(i) => Ok(list.remove(i)),
+        None => Err(error("IndexError: pop index out of range".to_owned(), name)),
+    }
+}
+
+fn list_insert(
+    _: &mut Interpreter,
+    list: &ListRef,
+    args: Args,
+    name: &Name,
+) -> Result<Value, PyError> {
+    let mut args = list_args(args, "list.insert", name, 2, 2)?;
+    let idx = expect_int(&args[0], "list.insert", name)?;
+    let mut list = list.borrow_mut();
+    // like slices, indices outside of the list go to the start or end
+    let idx = clamp_index(idx, list.len());
+    list.insert(idx, args.remove(1));
+    Ok(Value::None)
+}
+
+fn list_remove(
+    int: &mut Interpreter,
+    list: &ListRef,
+    args: Args,
+    name: &Name,
+) -> Result<Value, PyError> {
+    let args = list_args(args, "list.remove", name, 1, 1)?;
+    // only borrowed mutably after comparing, the elements could contain the list
+    let len = list.borrow().len();
+    match position(int, list, 0, len, &args[0], name)? {
+        Some(i) => {
+            list.borrow_mut().remove(i);
+            Ok(Value::None)
+        }
+        None => Err(error(
+            "ValueError: list.remove(x): x not in list".to_owned(),
+            name,
+        )),
+    }
+}
+
+fn list_extend(
+    _: &mut Interpreter,
+    list: &ListRef,
+    args: Args,
+    name: &Name,
+) -> Result<Value, PyError> {
+    let args = list_args(args, "list.extend", name, 1, 1)?;
+    // collected first, because a list can be extended with itself
+    let elems = match args[0].iterate() {
+        Some(elems) => elems.map_err(|msg| error(msg, name))?,
+        None => {
+            return Err(error(
+                format!(
+                    "TypeError: '{}' object is not iterable",
+                    args[0].type_name()
+                ),
+                name,
+            ))
+        }
+    };
+    list.borrow_mut().extend(elems);
+    Ok(Value::None)
+}
+
+// sort(key=None, reverse=False), only takes keyword arguments
+fn list_sort(
+    int: &mut Interpreter,
+    list: &ListRef,
+    mut args: Args,
+    name: &Name,
+) -> Result<Value, PyError> {
+    let key = args.take_keyword("key").unwrap_or(Value::None);
+    let reverse = args.take_keyword("reverse").is_some_and(|v| v.to_bool());
+    args.reject_remaining("sort", name)?;
+    if !args.positional.is_empty() {
+        return Err(error(
+            "TypeError: sort() takes no positional arguments".to_owned(),
+            name,
+        ));
+    }
+
+    // the list isn't borrowed while the key function runs, it could use the list itself
+    let elems = list.borrow().clone();
+    let mut keyed = Vec::new();
+    for v in elems {
+        let k = match key {
+            Value::None => v.clone(),
+            _ => int.call_value(key.clone(), vec![v.clone()], Vec::new(), name)?,
+        };
+        keyed.push((k, v));
+    }
+    let sorted = merge_sort(int, keyed, reverse, name)?;
+    *list.borrow_mut() = sorted.into_iter().map(|(_, v)| v).collect();
+    Ok(Value::None)
+}
+
+// stable merge sort of (key, value) pairs by key, since comparing two keys can fail
+fn merge_sort(
+    int: &Interpreter,
+    mut items: Vec<(Value, Value)>,
+    reverse: bool,
+    name: &Name,
+) -> Result<Vec<(Value, Value)>, PyError> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+    let right = items.split_off(items.len() / 2);
+    let mut left = merge_sort(int, items, reverse, name)?
+        .into_iter()
+        .peekable();
+    let mut right = merge_sort(int, right, reverse, name)?
+        .into_iter()
+        .peekable();
+
+    let mut res = Vec::new();
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        // equal elements are taken from the left first to keep their order, also when reversed
+        let take_right = if reverse {
+            less_than(int, &l.0, &r.0, name)?
+        } else {
+            less_than(int, &r.0, &l.0, name)?
+        };
+        if take_right {
+            res.extend(right.next());
+        } else {
+            res.extend(left.next());
+        }
+    }
+    res.extend(left);
+    res.extend(right);
+    Ok(res)
+}
+
+fn less_than(int: &Interpreter, a: &Value, b: &Value, name: &Name) -> Result<bool, PyError> {
+    let limits = &mut int.limits.borrow_mut();
+    if let Some((ta, tb)) = a.unorderable_types(b, limits).map_err(|e| error(e, name))? {
+        return Err(error(
+            format!("TypeError: '<' not supported between instances of '{ta}' and '{tb}'"),
+            name,
+        ));
+    }
+    let ord = a.compare(b, limits).map_err(|e| error(e, name))?;
+    Ok(ord == Some(Ordering::Less))
+}
+
+// index of the first element between start and end that is equal to x
+fn position(
+    int: &Interpreter,
+    list: &ListRef,
+    start: usize,
+    end: usize,
+    x: &Value,
+    name: &Name,
+) -> Result<Option<usize>, PyError> {
+    let list = list.borrow();
+    let limits = &mut int.limits.borrow_mut();
+    for (i, v) in list.iter().enumerate().take(end).skip(start) {
+        if v.equals(x, limits).map_err(|e| error(e, name))? {
+            return Ok(Some(i));
+        }
+    }
+    Ok(None)
+}
+
+fn list_reverse(
+    _: &mut Interpreter,
+    list: &ListRef,
+    args: Args,
+    name: &Name,
+) -> Result<Value, PyError> {
+    list_args(args, "list.reverse", name, 0, 0)?;
+    list.borrow_mut().reverse();
+    Ok(Value::None)
+}
+
+// index(x, start=0, end=len), the position of the first x between start and end
+fn list_index(
+    int: &mut Interpreter,
+    list: &ListRef,
+    args: Args,
+    name: &Name,
+) -> Result<Value, PyError> {
+    let args = list_args(args, "list.index", name, 1, 3)?;
+    let len = list.borrow().len();
+    let start = match args.get(1) {
+        Some(v) => clamp_index(expect_int(v, "list.index", name)?, len),
+        None => 0,
+    };
+    let end = match args.get(2) {
+        Some(v) => clamp_index(expect_int(v, "list.index", name)?, len),
+        None => len,
+    };
+    match position(int, list, start, end, &args[0], name)? {
+        Some(i) => Ok(Value::Int(i as i128)),
+        None => {
+            let val = args[0]
+                .repr(&mut int.limits.borrow_mut())
+                .map_err(|msg| error(msg, name))?;
+            Err(error(format!("ValueError: {val} is not in list"), name))
+        }
+    }
+}
+
+fn list_count(
+    int: &mut Interpreter,
+    list: &ListRef,
+    args: Args,
+    name: &Name,
+) -> Result<Value, PyError> {
+    let args = list_args(args, "list.count", name, 1, 1)?;
+    let limits = &mut int.limits.borrow_mut();
+    let mut n = 0;
+    for v in list.borrow().iter() {
+        if v.equals(&args[0], limits).map_err(|e| error(e, name))? {
+            n += 1;
+        }
+    }
+    Ok(Value::Int(n))
+}
+
+//////////////////
+// file methods //
+//////////////////
+
+// read(size=-1), a negative size or None reads everything
+fn file_read(file: &FileRef, args: Vec<Value>, name: &Name) -> Result<Value, PyError> {
+    check_arity("read", name, &args, 0, 1)?;
+    let size = match args.first() {
+        None | Some(Value::None) => None,
+        Some(v) => usize::try_from(expect_int(v, "read", name)?).ok(),
+    };
+    let text = file
+        .borrow_mut()
+        .read(size)
+        .map_err(|msg| error(msg, name))?;
+    Ok(Value::String(text))
+}
+
+fn file_readline(file: &FileRef, args: Vec<Value>, name: &Name) -> Result<Value, PyError> {
+    check_arity("readline", name, &args, 0, 0)?;
+    let line = file
+        .borrow_mut()
+        .readline()
+        .map_err(|msg| error(msg, name))?;
+    Ok(Value::String(line))
+}
+
+fn file_readlines(file: &FileRef, args: Vec<Value>, name: &Name) -> Result<Value, PyError> {
+    check_arity("readlines", name, &args, 0, 0)?;
+    let lines = file
+        .borrow_mut()
+        .readlines()
+        .map_err(|msg| error(msg, name))?;
+    Ok(Value::list(lines.into_iter().map(Value::String).collect()))
+}
+
+// write(s) returns the number of chars written
+fn file_write(file: &FileRef, args: Vec<Value>, name: &Name) -> Result<Value, PyError> {
+    check_arity("write", name, &args, 1, 1)?;
+    let Value::String(text) = &args[0] else {
+        return Err(error(
+            format!(
+                "TypeError: write() argument must be str, not {}",
+                args[0].type_name()
+            ),
+            name,
+        ));
+    };
+    file.borrow_mut()
+        .write(text)
+        .map_err(|msg| error(msg, name))?;
+    Ok(Value::Int(text.chars().count() as i128))
+}
+
+fn file_flush(file: &FileRef, args: Vec<Value>, name: &Name) -> Result<Value, PyError> {
+    check_arity("flush", name, &args, 0, 0)?;
+    file.borrow_mut().flush().map_err(|msg| error(msg, name))?;
+    Ok(Value::None)
+}
+
+fn file_close(file: &FileRef, args: Vec<Value>, name: &Name) -> Result<Value, PyError> {
+    check_arity("close", name, &args, 0, 0)?;
+    file.borrow_mut().close().map_err(|msg| error(msg, name))?;
+    Ok(Value::None)
+}
+
+fn file_enter(file: &FileRef, args: Vec<Value>, name: &Name) -> Result<Value, PyError> {
+    check_arity("__enter__", name, &args, 0, 0)?;
+    if file.borrow().closed() {
+        return Err(error(
+            "ValueError: I/O operation on closed file.".to_owned(),
+            name,
+        ));
+    }
+    Ok(Value::File(file.clone()))
+}
+
+// __exit__(exc_type, exc_value, traceback) closes the file without suppressing the error
+fn file_exit(file: &FileRef, args: Vec<Value>, name: &Name) -> Result<Value, PyError> {
+    check_arity("__exit__", name, &args, 3, 3)?;
+    file.borrow_mut().close().map_err(|msg| error(msg, name))?;
+    Ok(Value::None)
+}
@@ -0,0 +1,57 @@
+// modules imported with import and from ... import
+// every module is only run once, later imports get the cached one
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env, fmt,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::interpreter::Value;
+
+pub struct Module {
+    pub name: String,
+    // the global variables of the module after running it
+    pub vars: RefCell<HashMap<String, Value>>,
+}
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<module '{}'>", self.name)
+    }
+}
+
+pub struct Modules {
+    search_paths: Vec<PathBuf>,
+    // command line arguments of the script for sys.argv, starting with its path
+    pub argv: Vec<String>,
+    pub loaded: HashMap<String, Rc<Module>>,
+    // modules that are currently being run, importing one of them again is a circular import
+    pub loading: Vec<String>,
+}
+
+impl Modules {
+    // modules are searched next to the script first, then in the directories of PYSUBSET_PATH
+    pub fn new(script_dir: &Path, argv: Vec<String>) -> Self {
+        let mut search_paths = vec![script_dir.to_path_buf()];
+        if let Some(paths) = env::var_os("PYSUBSET_PATH") {
+            search_paths.extend(env::split_paths(&paths));
+        }
+        Modules {
+            search_paths,
+            argv,
+            loaded: HashMap::new(),
+            loading: Vec::new(),
+        }
+    }
+
+    // path of the file name.py in the first search path that contains it
+    pub fn find(&self, name: &str) -> Option<PathBuf> {
+        self.search_paths
+            .iter()
+            .map(|dir| dir.join(format!("{name}.py")))
+            .find(|path| path.is_file())
+    }
+}
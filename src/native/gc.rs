@@ -0,0 +1,45 @@
+// gc: collect, enable, disable and isenabled, see interpreter/heap.rs
+
+use crate::{
+    builtins::{check_arity, Args},
+    common::{ast::Name, py_error::PyError},
+    interpreter::{heap, Interpreter, Value},
+    modules::Modules,
+};
+
+pub fn init(_: &Modules) -> Vec<(&'static str, Value)> {
+    vec![
+        ("collect", Value::Builtin("collect", collect)),
+        ("enable", Value::Builtin("enable", enable)),
+        ("disable", Value::Builtin("disable", disable)),
+        ("isenabled", Value::Builtin("isenabled", isenabled)),
+    ]
+}
+
+// collect() frees the objects that are only kept alive by reference cycles and returns how many
+fn collect(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("collect", name)?;
+    check_arity("collect", name, &args, 0, 0)?;
+    Ok(Value::Int(heap::collect() as i128))
+}
+
+// enable() and disable() turn the automatic collection on and off
+fn enable(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("enable", name)?;
+    check_arity("enable", name, &args, 0, 0)?;
+    heap::set_enabled(true);
+    Ok(Value::None)
+}
+
+fn disable(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("disable", name)?;
+    check_arity("disable", name, &args, 0, 0)?;
+    heap::set_enabled(false);
+    Ok(Value::None)
+}
+
+fn isenabled(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("isenabled", name)?;
+    check_arity("isenabled", name, &args, 0, 0)?;
+    Ok(Value::Bool(heap::is_enabled()))
+}
@@ -0,0 +1,103 @@
+// math: sqrt, floor, ceil, gcd, pi and e
+
+use std::f64::consts;
+
+use crate::{
+    builtins::{check_arity, error, Args},
+    common::{ast::Name, py_error::PyError},
+    interpreter::{Interpreter, Value},
+    modules::Modules,
+};
+
+pub fn init(_: &Modules) -> Vec<(&'static str, Value)> {
+    vec![
+        ("pi", Value::Float(consts::PI)),
+        ("e", Value::Float(consts::E)),
+        ("sqrt", Value::Builtin("sqrt", sqrt)),
+        ("floor", Value::Builtin("floor", floor)),
+        ("ceil", Value::Builtin("ceil", ceil)),
+        ("gcd", Value::Builtin("gcd", gcd)),
+    ]
+}
+
+fn real(val: &Value, name: &Name) -> Result<f64, PyError> {
+    match val {
+        Value::Int(n) => Ok(*n as f64),
+        Value::Bool(b) => Ok(*b as i128 as f64),
+        Value::Float(x) => Ok(*x),
+        v => Err(error(
+            format!("TypeError: must be real number, not {}", v.type_name()),
+            name,
+        )),
+    }
+}
+
+fn sqrt(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("sqrt", name)?;
+    check_arity("sqrt", name, &args, 1, 1)?;
+    let x = real(&args[0], name)?;
+    if x < 0.0 {
+        return Err(error("ValueError: math domain error".to_owned(), name));
+    }
+    Ok(Value::Float(x.sqrt()))
+}
+
+// floor and ceil return ints, which floats like inf and nan can't be converted to
+fn to_int(x: f64, name: &Name) -> Result<Value, PyError> {
+    if x.is_nan() {
+        return Err(error(
+            "ValueError: cannot convert float NaN to integer".to_owned(),
+            name,
+        ));
+    }
+    if x.is_infinite() {
+        return Err(error(
+            "OverflowError: cannot convert float infinity to integer".to_owned(),
+            name,
+        ));
+    }
+    Ok(Value::Int(x as i128))
+}
+
+fn floor(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("floor", name)?;
+    check_arity("floor", name, &args, 1, 1)?;
+    match &args[0] {
+        Value::Int(n) => Ok(Value::Int(*n)),
+        v => to_int(real(v, name)?.floor(), name),
+    }
+}
+
+fn ceil(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("ceil", name)?;
+    check_arity("ceil", name, &args, 1, 1)?;
+    match &args[0] {
+        Value::Int(n) => Ok(Value::Int(*n)),
+        v => to_int(real(v, name)?.ceil(), name),
+    }
+}
+
+// gcd(*integers), the result is never negative and gcd() is 0
+fn gcd(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("gcd", name)?;
+    let mut res: u128 = 0;
+    for arg in &args {
+        let mut n = match arg {
+            Value::Int(n) => n.unsigned_abs(),
+            Value::Bool(b) => *b as u128,
+            v => {
+                return Err(error(
+                    format!(
+                        "TypeError: '{}' object cannot be interpreted as an integer",
+                        v.type_name()
+                    ),
+                    name,
+                ))
+            }
+        };
+        while n != 0 {
+            (res, n) = (n, res % n);
+        }
+    }
+    Ok(Value::Int(res as i128))
+}
@@ -0,0 +1,27 @@
+// modules implemented in rust, they are imported like the ones written in python
+// a module is created from the list of its functions and constants
+
+mod gc;
+mod math;
+mod random;
+pub mod sys;
+mod time;
+
+use crate::{interpreter::Value, modules::Modules};
+
+type Init = fn(&Modules) -> Vec<(&'static str, Value)>;
+
+const NATIVE_MODULES: &[(&str, Init)] = &[
+    ("math", math::init),
+    ("random", random::init),
+    ("time", time::init),
+    ("sys", sys::init),
+    ("gc", gc::init),
+];
+
+pub fn lookup(name: &str) -> Option<Init> {
+    NATIVE_MODULES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, init)| *init)
+}
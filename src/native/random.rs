@@ -0,0 +1,169 @@
+// random: randint, choice, random and seed
+// the numbers come from a xorshift generator, the same seed always gives the same numbers
+
+use std::{
+    cell::Cell,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    builtins::{check_arity, error, Args},
+    common::{ast::Name, py_error::PyError},
+    interpreter::{Interpreter, Value},
+    modules::Modules,
+};
+
+thread_local! {
+    // never 0, xorshift would only produce zeros from it
+    static STATE: Cell<u64> = Cell::new(time_seed());
+}
+
+pub fn init(_: &Modules) -> Vec<(&'static str, Value)> {
+    vec![
+        ("seed", Value::Builtin("seed", seed)),
+        ("random", Value::Builtin("random", random)),
+        ("randint", Value::Builtin("randint", randint)),
+        ("choice", Value::Builtin("choice", choice)),
+    ]
+}
+
+fn time_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    scramble(nanos)
+}
+
+// spreads similar seeds (e.g. 1 and 2) over the whole state, see splitmix64
+fn scramble(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    (z ^ (z >> 31)).max(1)
+}
+
+// xorshift64*
+fn next() -> u64 {
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        x.wrapping_mul(0x2545f4914f6cdd1d)
+    })
+}
+
+fn next_u128() -> u128 {
+    (next() as u128) << 64 | next() as u128
+}
+
+// random number in 0..n
+fn below(n: u128) -> u128 {
+    next_u128() % n
+}
+
+// seed(a=None), without a seed the current time is used
+fn seed(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("seed", name)?;
+    check_arity("seed", name, &args, 0, 1)?;
+    let s = match args.first() {
+        None | Some(Value::None) => time_seed(),
+        Some(Value::Int(n)) => scramble(*n as u64),
+        Some(Value::Bool(b)) => scramble(*b as u64),
+        Some(Value::Float(x)) => scramble(x.to_bits()),
+        // strings are hashed with FNV-1a, so they give the same numbers on every run
+        Some(Value::String(s)) => scramble(s.bytes().fold(0xcbf29ce484222325, |h, b| {
+            (h ^ b as u64).wrapping_mul(0x100000001b3)
+        })),
+        Some(v) => {
+            return Err(error(
+                format!(
+                "TypeError: The only supported seed types are: None, int, float, str, but got {}",
+                v.type_name()
+            ),
+                name,
+            ))
+        }
+    };
+    STATE.with(|state| state.set(s));
+    Ok(Value::None)
+}
+
+// random() is a float in [0, 1)
+fn random(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("random", name)?;
+    check_arity("random", name, &args, 0, 0)?;
+    // the upper 53 bits fill the mantissa
+    Ok(Value::Float((next() >> 11) as f64 / (1u64 << 53) as f64))
+}
+
+// randint(a, b) is an int in [a, b]
+fn randint(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("randint", name)?;
+    check_arity("randint", name, &args, 2, 2)?;
+    let mut bounds = [0; 2];
+    for (bound, arg) in bounds.iter_mut().zip(&args) {
+        *bound = match arg {
+            Value::Int(n) => *n,
+            Value::Bool(b) => *b as i128,
+            v => {
+                return Err(error(
+                    format!(
+                        "TypeError: '{}' object cannot be interpreted as an integer",
+                        v.type_name()
+                    ),
+                    name,
+                ))
+            }
+        };
+    }
+    let [a, b] = bounds;
+    if a > b {
+        return Err(error(
+            format!("ValueError: empty range in randrange({a}, {})", b + 1),
+            name,
+        ));
+    }
+    // only the range of all i128s doesn't fit into a u128
+    let offset = match b.abs_diff(a).checked_add(1) {
+        Some(width) => below(width),
+        None => next_u128(),
+    };
+    Ok(Value::Int(a.wrapping_add(offset as i128)))
+}
+
+// choice(seq) is a random element of a list or char of a string
+fn choice(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("choice", name)?;
+    check_arity("choice", name, &args, 1, 1)?;
+    let empty = || {
+        error(
+            "IndexError: Cannot choose from an empty sequence".to_owned(),
+            name,
+        )
+    };
+    match &args[0] {
+        Value::List(l) => {
+            let l = l.borrow();
+            if l.is_empty() {
+                return Err(empty());
+            }
+            Ok(l[below(l.len() as u128) as usize].clone())
+        }
+        Value::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            if chars.is_empty() {
+                return Err(empty());
+            }
+            Ok(Value::String(
+                chars[below(chars.len() as u128) as usize].to_string(),
+            ))
+        }
+        v => Err(error(
+            format!("TypeError: object of type '{}' has no len()", v.type_name()),
+            name,
+        )),
+    }
+}
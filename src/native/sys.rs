@@ -0,0 +1,100 @@
+// sys: argv, exit and the recursion limit
+
+use std::io::Write;
+
+use crate::{
+    builtins::{check_arity, error, Args},
+    common::{ast::Name, py_error::PyError},
+    interpreter::{Interpreter, Value},
+    modules::Modules,
+};
+
+// exit() stops the script with an error carrying the exit code, since there is no way to catch it
+const EXIT_PREFIX: &str = "SystemExit: ";
+
+pub fn init(modules: &Modules) -> Vec<(&'static str, Value)> {
+    let argv = modules.argv.iter().cloned().map(Value::String).collect();
+    vec![
+        ("argv", Value::list(argv)),
+        ("exit", Value::Builtin("exit", exit)),
+        (
+            "getrecursionlimit",
+            Value::Builtin("getrecursionlimit", getrecursionlimit),
+        ),
+        (
+            "setrecursionlimit",
+            Value::Builtin("setrecursionlimit", setrecursionlimit),
+        ),
+    ]
+}
+
+// the exit code if the error comes from exit()
+pub fn exit_code(e: &PyError) -> Option<i32> {
+    e.msg.strip_prefix(EXIT_PREFIX)?.parse().ok()
+}
+
+// exit(status=None), a status that isn't an int is printed and exits with 1
+fn exit(int: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("exit", name)?;
+    check_arity("exit", name, &args, 0, 1)?;
+    let code = match args.first() {
+        None | Some(Value::None) => 0,
+        Some(Value::Int(n)) => *n as i32,
+        Some(Value::Bool(b)) => *b as i32,
+        Some(v) => {
+            let text = v
+                .str(&mut int.limits.borrow_mut())
+                .map_err(|msg| error(msg, name))?;
+            let mut streams = int.streams.borrow_mut();
+            let _ = streams.stdout.flush();
+            let _ = writeln!(streams.stderr, "{text}");
+            1
+        }
+    };
+    Err(error(format!("{EXIT_PREFIX}{code}"), name))
+}
+
+fn getrecursionlimit(int: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("getrecursionlimit", name)?;
+    check_arity("getrecursionlimit", name, &args, 0, 0)?;
+    Ok(Value::Int(int.limits.borrow().recursion_limit as i128))
+}
+
+// setrecursionlimit(limit), the limit has to be above the current depth
+fn setrecursionlimit(int: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("setrecursionlimit", name)?;
+    check_arity("setrecursionlimit", name, &args, 1, 1)?;
+    let limit = match &args[0] {
+        Value::Int(n) => *n,
+        Value::Bool(b) => *b as i128,
+        v => {
+            return Err(error(
+                format!(
+                    "TypeError: '{}' object cannot be interpreted as an integer",
+                    v.type_name()
+                ),
+                name,
+            ))
+        }
+    };
+    if limit < 1 {
+        return Err(error(
+            "ValueError: recursion limit must be greater or equal than 1".to_owned(),
+            name,
+        ));
+    }
+    let mut limits = int.limits.borrow_mut();
+    // very high limits are still stopped by the size of the stack
+    let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+    if limit <= limits.depth {
+        return Err(error(
+            format!(
+                "RecursionError: cannot set the recursion limit to {limit} at the recursion depth {}: the limit is too low",
+                limits.depth
+            ),
+            name,
+        ));
+    }
+    limits.recursion_limit = limit;
+    Ok(Value::None)
+}
@@ -0,0 +1,43 @@
+// time: time and perf_counter
+
+use std::{
+    sync::OnceLock,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    builtins::{check_arity, Args},
+    common::{ast::Name, py_error::PyError},
+    interpreter::{Interpreter, Value},
+    modules::Modules,
+};
+
+// perf_counter counts from the first time the module is imported
+static START: OnceLock<Instant> = OnceLock::new();
+
+pub fn init(_: &Modules) -> Vec<(&'static str, Value)> {
+    START.get_or_init(Instant::now);
+    vec![
+        ("time", Value::Builtin("time", time)),
+        ("perf_counter", Value::Builtin("perf_counter", perf_counter)),
+    ]
+}
+
+// seconds since the epoch
+fn time(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("time", name)?;
+    check_arity("time", name, &args, 0, 0)?;
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0);
+    Ok(Value::Float(secs))
+}
+
+// seconds of a monotonic clock, only useful for measuring durations
+fn perf_counter(_: &mut Interpreter, args: Args, name: &Name) -> Result<Value, PyError> {
+    let args = args.positional_only("perf_counter", name)?;
+    check_arity("perf_counter", name, &args, 0, 0)?;
+    let start = START.get_or_init(Instant::now);
+    Ok(Value::Float(start.elapsed().as_secs_f64()))
+}
@@ -0,0 +1,233 @@
+// optimizations of the syntax tree before it runs, turned off with --no-optimize to compare the results
+// operators on literals are computed once, unless that raises an error, which is left for when the code runs
+// and code that can never run is dropped
+
+use std::rc::Rc;
+
+use crate::{
+    common::ast::*,
+    interpreter::{Interpreter, Value},
+    limits::Limits,
+    resolver,
+};
+
+pub fn optimize(stmts: Vec<Stmt>) -> Vec<Stmt> {
+    block(stmts, false)
+}
+
+// the names a function assigns are its local variables (see resolver.rs), even when the assignment never runs,
+// so inside of functions dead code that assigns names is kept
+fn block(stmts: Vec<Stmt>, in_function: bool) -> Vec<Stmt> {
+    let mut res = Vec::new();
+    let mut reachable = true;
+    for st in stmts {
+        for st in statement(st, in_function) {
+            if reachable {
+                // nothing after a return runs
+                reachable = !matches!(st, Stmt::Return(..));
+                res.push(st);
+            } else if in_function && binds_names(std::slice::from_ref(&st)) {
+                res.push(st);
+            }
+        }
+    }
+    res
+}
+
+fn binds_names(stmts: &[Stmt]) -> bool {
+    let mut names = Vec::new();
+    resolver::bound_names(stmts, &mut names);
+    !names.is_empty()
+}
+
+// an if with a constant condition is replaced by the branch that runs, so there can be several statements
+fn statement(stmt: Stmt, in_function: bool) -> Vec<Stmt> {
+    match stmt {
+        Stmt::Expr(e) => {
+            let e = expr(e);
+            // a lone literal does nothing
+            if constant(&e).is_some() {
+                Vec::new()
+            } else {
+                vec![Stmt::Expr(e)]
+            }
+        }
+        Stmt::AssignVar(n, e) => vec![Stmt::AssignVar(n, expr(e))],
+        Stmt::AssignList(n, i, e) => vec![Stmt::AssignList(n, expr(i), expr(e))],
+        Stmt::If(l, c, t, e) => {
+            let c = expr(c);
+            let t = block(t, in_function);
+            let e = e.map(|e| block(e, in_function));
+            let Some(cond) = constant(&c).map(|c| c.to_bool()) else {
+                return vec![Stmt::If(l, c, t, e)];
+            };
+            let dropped = if cond {
+                e.as_deref().unwrap_or_default()
+            } else {
+                &t
+            };
+            if in_function && binds_names(dropped) {
+                vec![Stmt::If(l, c, t, e)]
+            } else if cond {
+                t
+            } else {
+                e.unwrap_or_default()
+            }
+        }
+        Stmt::While(l, c, b) => {
+            let c = expr(c);
+            let b = block(b, in_function);
+            let never = constant(&c).is_some_and(|c| !c.to_bool());
+            if never && !(in_function && binds_names(&b)) {
+                Vec::new()
+            } else {
+                vec![Stmt::While(l, c, b)]
+            }
+        }
+        Stmt::With(l, e, a, b) => vec![Stmt::With(l, expr(e), a, block(b, in_function))],
+        Stmt::FunDecl(l, n, p, b) => {
+            let b = block(Rc::unwrap_or_clone(b), true);
+            vec![Stmt::FunDecl(l, n, p, Rc::new(b))]
+        }
+        Stmt::Return(l, e) => vec![Stmt::Return(l, e.map(expr))],
+        st @ (Stmt::Import(..) | Stmt::FromImport(..)) => vec![st],
+    }
+}
+
+fn expr(ex: Expr) -> Expr {
+    match ex {
+        Expr::Unary(op, e) => {
+            let e = Box::new(expr(*e));
+            let span = op.span.to(e.span());
+            let folded = constant(&e).and_then(|v| {
+                let (line, column) = (op.line, op.column);
+                let res = Interpreter::unary_op(op.clone(), v).ok()?;
+                to_expr(res, line, column, span)
+            });
+            folded.unwrap_or(Expr::Unary(op, e))
+        }
+        Expr::Binary(e1, op, e2) => {
+            let (e1, e2) = (Box::new(expr(*e1)), Box::new(expr(*e2)));
+            let span = e1.span().to(e2.span());
+            let folded = constant(&e1).zip(constant(&e2)).and_then(|(a, b)| {
+                let res = binary_op(a, &op, b)?;
+                to_expr(res, op.line, op.column, span)
+            });
+            folded.unwrap_or(Expr::Binary(e1, op, e2))
+        }
+        Expr::Compare(e, rest) => {
+            let e = Box::new(expr(*e));
+            let rest: Vec<(BiOp, Expr)> = rest.into_iter().map(|(op, e)| (op, expr(e))).collect();
+            compare(&e, &rest).unwrap_or(Expr::Compare(e, rest))
+        }
+        // only there for the order of operations
+        Expr::Grouping(e, _) => expr(*e),
+        Expr::Literal(Lit::List(elems), s) => Expr::Literal(Lit::List(exprs(elems)), s),
+        e @ (Expr::Literal(..) | Expr::Variable(_)) => e,
+        Expr::Call(n, a, k, s) => Expr::Call(n, exprs(a), keywords(k), s),
+        Expr::ListAccess(t, i, l, s) => {
+            Expr::ListAccess(Box::new(expr(*t)), Box::new(expr(*i)), l, s)
+        }
+        Expr::MethodCall(o, n, a, k, s) => {
+            Expr::MethodCall(Box::new(expr(*o)), n, exprs(a), keywords(k), s)
+        }
+        Expr::Attribute(o, n) => Expr::Attribute(Box::new(expr(*o)), n),
+        Expr::FString(parts, s) => Expr::FString(fstring(parts), s),
+        Expr::Slice(a, b, c, s) => Expr::Slice(
+            a.map(|e| Box::new(expr(*e))),
+            b.map(|e| Box::new(expr(*e))),
+            c.map(|e| Box::new(expr(*e))),
+            s,
+        ),
+    }
+}
+
+fn exprs(es: Vec<Expr>) -> Vec<Expr> {
+    es.into_iter().map(expr).collect()
+}
+
+fn keywords(k: Vec<(Name, Expr)>) -> Vec<(Name, Expr)> {
+    k.into_iter().map(|(n, e)| (n, expr(e))).collect()
+}
+
+fn fstring(parts: Vec<FStringPart>) -> Vec<FStringPart> {
+    parts
+        .into_iter()
+        .map(|part| match part {
+            FStringPart::Field(e, conv, spec, loc) => {
+                FStringPart::Field(Box::new(expr(*e)), conv, fstring(spec), loc)
+            }
+            lit => lit,
+        })
+        .collect()
+}
+
+// lists aren't constants, so the limits for comparing nested lists are never reached here
+fn binary_op(left: Value, op: &BiOp, right: Value) -> Option<Value> {
+    let limits = &mut Limits::unlimited();
+    Interpreter::binary_op(left, op.clone(), right, limits).ok()
+}
+
+// a chain like 1 < 2 < 3 of constants, compared like the interpreters do it
+fn compare(first: &Expr, rest: &[(BiOp, Expr)]) -> Option<Expr> {
+    let mut left = constant(first)?;
+    let mut res = Value::Bool(true);
+    let (line, column) = rest.first().map(|(op, _)| (op.line, op.column))?;
+    let span = first.span().to(rest.last()?.1.span());
+    for (op, e) in rest {
+        let right = constant(e)?;
+        res = binary_op(left, op, right.clone())?;
+        if !res.to_bool() {
+            break;
+        }
+        left = right;
+    }
+    to_expr(res, line, column, span)
+}
+
+// the value of a literal, lists aren't constant since every evaluation makes a new one
+// negative numbers are literals with a minus in front
+fn constant(expr: &Expr) -> Option<Value> {
+    match expr {
+        Expr::Literal(lit, _) => match lit {
+            Lit::Int(n) => Some(Value::Int((*n).into())),
+            Lit::Float(x) => Some(Value::Float(*x)),
+            Lit::String(s) => Some(Value::String(s.clone())),
+            Lit::True => Some(Value::Bool(true)),
+            Lit::False => Some(Value::Bool(false)),
+            Lit::None => Some(Value::None),
+            Lit::List(_) => None,
+        },
+        Expr::Unary(op, e) if matches!(op.ty, UnOpType::Minus) => match constant(e)? {
+            Value::Int(n) => Some(Value::Int(-n)),
+            Value::Float(x) => Some(Value::Float(-x)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// the literal for a computed value, None if there is none, e.g. for ints bigger than literals can be
+// it gets the span of the expression it replaces
+fn to_expr(val: Value, line: u64, column: u64, span: Span) -> Option<Expr> {
+    let lit = match val {
+        Value::Int(n) if n < 0 => {
+            let minus = UnOp {
+                ty: UnOpType::Minus,
+                line,
+                column,
+                span,
+            };
+            let abs = Lit::Int(u64::try_from(n.unsigned_abs()).ok()?);
+            return Some(Expr::Unary(minus, Box::new(Expr::Literal(abs, span))));
+        }
+        Value::Int(n) => Lit::Int(u64::try_from(n).ok()?),
+        Value::Float(x) => Lit::Float(x),
+        Value::String(s) => Lit::String(s),
+        Value::Bool(true) => Lit::True,
+        Value::Bool(false) => Lit::False,
+        Value::None => Lit::None,
+        _ => return None,
+    };
+    Some(Expr::Literal(lit, span))
+}
@@ -1,12 +1,24 @@
+use std::rc::Rc;
+
 use crate::common::{ast::*, py_error::PyError, token::*};
 
+// positional and keyword arguments of a call
+type Arguments = (Vec<Expr>, Vec<(Name, Expr)>);
+
+// how deep expressions and blocks may be nested, like python's limits of nested parentheses and indentation,
+// deeper code would overflow the stack of the parser or of everything that walks the syntax tree afterwards
+const MAX_NESTING: usize = 200;
+// blocks are never inside of expressions, so only blocks count when one starts
+const MAX_INDENT: usize = 100;
+
 pub fn parse(tokens: Vec<Token>) -> Option<Vec<Stmt>> {
     let mut p = Parser {
         tokens,
         current_idx: 0,
+        errors: Vec::new(),
+        depth: 0,
     };
     let mut statements = Vec::new();
-    let mut error = false;
 
     while !p.check_type(vec![TokenType::EndOfFile]) {
         // ignore end of lines that haven't been parsed in a rule in order to ignore blank lines
@@ -16,29 +28,30 @@ pub fn parse(tokens: Vec<Token>) -> Option<Vec<Stmt>> {
         match p.statement() {
             Ok(s) => statements.push(s),
             Err(e) => {
-                // NOTE: not harsh enough because some statements span more than one line
-                // because of this parser breaks for now to prevent cascading errors
-                // advance to next line on error
-                // while !p.check_type(vec![TokenType::EndOfLine, TokenType::EndOfFile]) {
-                //     p.current_idx += 1;
-                // }
-                error = true;
-                println!("{e}");
-                break;
+                // skip the rest of the broken statement so parsing can continue with the next one
+                p.errors.push(e);
+                p.synchronize();
             },
         }
     }
 
-    if error {
-        None
-    } else {
+    if p.errors.is_empty() {
         Some(statements)
+    } else {
+        for e in &p.errors {
+            println!("{e}");
+        }
+        None
     }
 }
 
 struct Parser {
     tokens: Vec<Token>,
     current_idx: usize,
+    // errors of statements that were skipped by synchronize()
+    errors: Vec<PyError>,
+    // expressions and blocks the parser is inside of, see MAX_NESTING
+    depth: usize,
 }
 
 impl Parser {
@@ -47,38 +60,13 @@ impl Parser {
     /////////////
     // see grammar.txt
 
-    // stmt -> exprStmt | printStmt | assignVarStmt | assignLsStmt | ifStmt | whileStmt | funDecl
+    // stmt -> exprStmt | assignVarStmt | assignLsStmt | ifStmt | whileStmt | withStmt | funDecl | importStmt | fromImportStmt
     fn statement(&mut self) -> Result<Stmt, PyError> {
-        if self.check_advance(vec![TokenType::Print]) {
-            return self.print_statement();
+        if self.check_advance(vec![TokenType::Import]) {
+            return self.import_statement();
         }
-        // only go to assignStmt if there is an id followed by =
-        if self.check_advance(vec![TokenType::Identifier("".to_owned())]) {
-            if self.check_advance(vec![TokenType::Equal]) {
-                return self.assign_var_statement();
-            } else {
-                // needed so idx points back at identifier and doesn't skip it
-                self.current_idx -= 1;
-            }
-        }
-        // not very pretty I fear
-        if self.check_advance(vec![TokenType::Identifier("".to_owned())]) {
-            if self.check_advance(vec![TokenType::LeftBracket]) {
-                let id = &self.tokens[self.current_idx - 2];
-                let name = Name { name: id.value.to_owned(), line: id.line, column: id.column };
-                let idx = self.expression()?;
-                self.check_or_error(vec![TokenType::RightBracket], "SyntaxError: expected closing bracket, found missing or unexpected token".to_owned())?;
-                if self.check_advance(vec![TokenType::Equal]) {
-                    return self.assign_list_statement(name, idx);
-                } else {
-                    self.current_idx -= 1;
-                    // NOTE: technically breaks something like a random "a[1] + 1" but I mean come on who just writes random expression statements in their code
-                    return Ok(Stmt::Expr(Expr::ListAccess(name, Box::new(idx))))
-                }
-            } else {
-                // needed so idx points back at identifier and doesn't skip it
-                self.current_idx -= 1;
-            }
+        if self.check_advance(vec![TokenType::From]) {
+            return self.import_from_statement();
         }
         if self.check_advance(vec![TokenType::If]) {
             return self.if_statement();
@@ -86,6 +74,9 @@ impl Parser {
         if self.check_advance(vec![TokenType::While]) {
             return self.while_statement();
         }
+        if self.check_advance(vec![TokenType::With]) {
+            return self.with_statement();
+        }
         if self.check_advance(vec![TokenType::Def]) {
             return self.function_declaration();
         }
@@ -97,30 +88,26 @@ impl Parser {
     }
 
     // exprStmt -> expr "\n"
+    // assignments are parsed here as well, since their target can only be told apart from an expression at the =
     fn expression_statement(&mut self) -> Result<Stmt, PyError> {
         let ex = self.expression()?;
+        if self.check_advance(vec![TokenType::Equal]) {
+            return match ex {
+                Expr::Variable(name) => self.assign_var_statement(name),
+                Expr::ListAccess(target, idx, ..) => match *target {
+                    Expr::Variable(name) => self.assign_list_statement(name, *idx),
+                    _ => Err(self.error_at_previous("SyntaxError: can only assign to an element of a list variable")),
+                },
+                _ => Err(self.error_at_previous("SyntaxError: cannot assign to expression")),
+            };
+        }
         self.check_or_error(vec![TokenType::EndOfLine], "SyntaxError: unexpected or missing token after statement (expected newline)".to_owned())?;
 
         Ok(Stmt::Expr(ex))
     }
 
-    // printStmt -> "print" "(" expr ")" "\n"
-    fn print_statement(&mut self) -> Result<Stmt, PyError> {
-        // print already consumed in statement
-        self.check_or_error(vec![TokenType::LeftParen], "SyntaxError: missing ( in call to print".to_owned())?;
-        let ex = self.expression()?;
-        self.check_or_error(vec![TokenType::RightParen], "SyntaxError: missing ) in call to print".to_owned())?;
-        self.check_or_error(vec![TokenType::EndOfLine], "SyntaxError: unexpected or missing token after statement (expected newline)".to_owned())?;
-
-        Ok(Stmt::Print(ex))
-    }
-
     // assignVarStmt -> IDENTIFIER "=" expr "\n"
-    fn assign_var_statement(&mut self) -> Result<Stmt, PyError> {
-        // guaranteed to be identifier because of ifs in statement()
-        let id = &self.tokens[self.current_idx - 2];
-        let name = Name {name: id.value.to_owned(), line: id.line, column: id.column };
-
+    fn assign_var_statement(&mut self, name: Name) -> Result<Stmt, PyError> {
         let ex = self.expression()?;
         self.check_or_error(vec![TokenType::EndOfLine], "SyntaxError: unexpected or missing token after statement (expected newline)".to_owned())?;
 
@@ -137,6 +124,8 @@ impl Parser {
 
     // ifStmt -> "if" expr ":" block ("else" ":" block)?
     fn if_statement(&mut self) -> Result<Stmt, PyError> {
+        let if_tok = self.tokens[self.current_idx - 1].clone();
+        let loc = Location {line: if_tok.line, column: if_tok.column, span: if_tok.span};
         let cond = self.expression()?;
         self.check_or_error(vec![TokenType::Colon], "SyntaxError: missing colon or expression after if statement".to_owned())?;
 
@@ -148,24 +137,55 @@ impl Parser {
             None
         };
 
-        Ok(Stmt::If(cond, then, maybe_else))
+        Ok(Stmt::If(loc, cond, then, maybe_else))
     }
 
     // whileStmt -> "while" expr ":" block
     fn while_statement(&mut self) -> Result<Stmt, PyError> {
+        let while_tok = self.tokens[self.current_idx - 1].clone();
+        let loc = Location {line: while_tok.line, column: while_tok.column, span: while_tok.span};
         let cond = self.expression()?;
         self.check_or_error(vec![TokenType::Colon], "SyntaxError: missing colon or expression after while statement".to_owned())?;
         let block = self.block()?;
-        Ok(Stmt::While(cond, block))
+        Ok(Stmt::While(loc, cond, block))
+    }
+
+    // withStmt -> "with" withItem ("," withItem)* ":" block
+    // withItem -> expr ("as" IDENTIFIER)?
+    fn with_statement(&mut self) -> Result<Stmt, PyError> {
+        let with_tok = self.tokens[self.current_idx - 1].clone();
+        let loc = Location {line: with_tok.line, column: with_tok.column, span: with_tok.span};
+        let mut items = Vec::new();
+        loop {
+            let manager = self.expression()?;
+            let alias = if self.check_advance(vec![TokenType::As]) {
+                Some(self.identifier("SyntaxError: missing name after as")?)
+            } else {
+                None
+            };
+            items.push((manager, alias));
+            if !self.check_advance(vec![TokenType::Comma]) {
+                break;
+            }
+        }
+        self.check_or_error(vec![TokenType::Colon], "SyntaxError: missing colon or expression after with statement".to_owned())?;
+        let mut block = self.block()?;
+
+        // with a, b: is the same as with a: with b:
+        while let Some((manager, alias)) = items.pop() {
+            block = vec![Stmt::With(loc.clone(), manager, alias, block)];
+        }
+        Ok(block.pop().expect("with statement has at least one item"))
     }
 
     // funDecl -> "def" IDENTIFIER "(" parameters? ")" ":" block
     fn function_declaration(&mut self) -> Result<Stmt, PyError> {
+        let loc = self.previous_location();
         self.check_or_error(vec![TokenType::Identifier("".to_owned())], "SyntaxError: missing name in def statement".to_owned())?;
         let id_tok = &self.tokens[self.current_idx - 1];
         let name;
         if let TokenType::Identifier(n) = &id_tok.token_type {
-            name = Name { name: n.to_owned(), line: id_tok.line, column: id_tok.column };
+            name = Name { name: n.to_owned(), line: id_tok.line, column: id_tok.column, span: id_tok.span };
         } else {
             panic!("expected Identifier token here");
         }
@@ -176,12 +196,12 @@ impl Parser {
         self.check_or_error(vec![TokenType::Colon], "SyntaxError: missing colon after def statement".to_owned())?;
         let body = self.block()?;
 
-        Ok(Stmt::FunDecl(name, params, body))
+        Ok(Stmt::FunDecl(loc, name, params, Rc::new(body)))
     }
 
     fn return_statement(&mut self) -> Result<Stmt, PyError> {
         let ret_tok = self.tokens[self.current_idx - 1].clone();
-        let loc = Location {line: ret_tok.line, column: ret_tok.column};
+        let loc = Location {line: ret_tok.line, column: ret_tok.column, span: ret_tok.span};
         if !self.check_advance(vec![TokenType::EndOfLine]) {
             let ex = self.expression()?;
             return Ok(Stmt::Return(loc, Some(ex)));
@@ -189,13 +209,55 @@ impl Parser {
         Ok(Stmt::Return(loc, None))
     }
 
+    // importStmt -> "import" importNames "\n"
+    fn import_statement(&mut self) -> Result<Stmt, PyError> {
+        let loc = self.previous_location();
+        let names = self.import_names("SyntaxError: missing module name in import statement")?;
+        self.check_or_error(vec![TokenType::EndOfLine], "SyntaxError: unexpected or missing token after statement (expected newline)".to_owned())?;
+        Ok(Stmt::Import(loc, names))
+    }
+
+    // fromImportStmt -> "from" IDENTIFIER "import" importNames "\n"
+    fn import_from_statement(&mut self) -> Result<Stmt, PyError> {
+        let loc = self.previous_location();
+        let module = self.identifier("SyntaxError: missing module name in from statement")?;
+        self.check_or_error(vec![TokenType::Import], "SyntaxError: missing import in from statement".to_owned())?;
+        let names = self.import_names("SyntaxError: missing name to import in from statement")?;
+        self.check_or_error(vec![TokenType::EndOfLine], "SyntaxError: unexpected or missing token after statement (expected newline)".to_owned())?;
+        Ok(Stmt::FromImport(loc, module, names))
+    }
+
+    // importNames -> IDENTIFIER ("as" IDENTIFIER)? ("," IDENTIFIER ("as" IDENTIFIER)?)*
+    fn import_names(&mut self, msg: &str) -> Result<Vec<(Name, Option<Name>)>, PyError> {
+        let mut names = Vec::new();
+        loop {
+            let name = self.identifier(msg)?;
+            let alias = if self.check_advance(vec![TokenType::As]) {
+                Some(self.identifier("SyntaxError: missing name after as")?)
+            } else {
+                None
+            };
+            names.push((name, alias));
+            if !self.check_advance(vec![TokenType::Comma]) {
+                return Ok(names);
+            }
+        }
+    }
+
+    // consumes an identifier and turns it into a Name
+    fn identifier(&mut self, msg: &str) -> Result<Name, PyError> {
+        self.check_or_error(vec![TokenType::Identifier("".to_owned())], msg.to_owned())?;
+        let id_tok = &self.tokens[self.current_idx - 1];
+        Ok(Name { name: id_tok.value.clone(), line: id_tok.line, column: id_tok.column, span: id_tok.span })
+    }
+
     // parameters -> IDENTIFIER ("," IDENTIFIER)*
     fn parameters(&mut self) -> Result<Vec<Name>, PyError> {
         let mut params = Vec::new();
         while !self.check_advance(vec![TokenType::RightParen]) {
             if self.check_advance(vec![TokenType::Identifier("".to_owned())]) {
                 let id_tok = &self.tokens[self.current_idx - 1];
-                params.push(Name { name: id_tok.value.clone(), line: id_tok.line, column: id_tok.column });
+                params.push(Name { name: id_tok.value.clone(), line: id_tok.line, column: id_tok.column, span: id_tok.span });
             }
             if self.check_advance(vec![TokenType::Comma]) {
                 // NOTE: this allows e.g. def f(a,), but python allows it too so no matter
@@ -213,27 +275,39 @@ impl Parser {
     // block -> "\n" INDENT stmt* DEDENT
     fn block(&mut self) -> Result<Vec<Stmt>, PyError> {
         self.check_or_error(vec![TokenType::EndOfLine], "SyntaxError: missing newline before block".to_owned())?;
-        self.check_or_error(vec![TokenType::Indent], "SyntaxError: missing indent before block".to_owned())?;
-           
+        // checked before the indent, so synchronize() skips the whole block
+        self.nested(MAX_INDENT, "IndentationError: too many levels of indentation", |p| {
+            p.check_or_error(vec![TokenType::Indent], "SyntaxError: missing indent before block".to_owned())?;
+            Ok(p.block_body())
+        })
+    }
+
+    // the stmt* DEDENT part of block, errors inside are collected so the rest of the block is still parsed
+    fn block_body(&mut self) -> Vec<Stmt> {
         let mut statements = Vec::new();
         while !self.check_advance(vec![TokenType::Dedent]) {
             // ignore end of lines that haven't been parsed in a rule in order to ignore blank lines
             if self.check_advance(vec![TokenType::EndOfLine]) {
                 continue;
             }
+            // the scanner closes every block before EOF, so this is only a safeguard against looping forever
+            if self.check_type(vec![TokenType::EndOfFile]) {
+                break;
+            }
             match self.statement() {
                 Ok(s) => statements.push(s),
                 Err(e) => {
-                    return Err(e);
+                    self.errors.push(e);
+                    self.synchronize();
                 },
             }
         }
-        Ok(statements)
+        statements
     }
 
     // expr -> equality
     fn expression(&mut self) -> Result<Expr, PyError> {
-        self.disjunction()
+        self.nested(MAX_NESTING, "SyntaxError: too many nested parentheses", Self::disjunction)
     }
 
     // disjunction -> conjunction ("and" conjunction)*
@@ -247,6 +321,7 @@ impl Parser {
                     ty: BiOpType::Or,
                     line: tok.line,
                     column: tok.column,
+                    span: tok.span,
                 },
                 _ => panic!("In disjunction(): op token_type was not Or, error probably in check_advance() or disjunction()"),
             };
@@ -256,9 +331,9 @@ impl Parser {
         Ok(ex)
     }
 
-    // conjunction -> equality ("and" equality)*
+    // conjunction -> comparison ("and" comparison)*
     fn conjunction(&mut self) -> Result<Expr, PyError> {
-        let mut ex = self.equality()?;
+        let mut ex = self.comparison()?;
         while self.check_advance(vec![TokenType::And]) {
             // turn the token into a BiOp
             let tok = &self.tokens[self.current_idx - 1];
@@ -267,78 +342,69 @@ impl Parser {
                     ty: BiOpType::And,
                     line: tok.line,
                     column: tok.column,
+                    span: tok.span,
                 },
                 _ => panic!("In conjunction(): op token_type was not And, error probably in check_advance() or conjunction()"),
             };
-            let right = self.equality()?;
-            ex = Expr::Binary(Box::new(ex), op, Box::new(right));
-        }
-        Ok(ex)
-    }
-
-    // equality -> comparison (("=="|"!=") comparison)*
-    fn equality(&mut self) -> Result<Expr, PyError> {
-        let mut ex = self.comparison()?;
-        while self.check_advance(vec![TokenType::DoubleEqual, TokenType::NotEqual]) {
-            // turn the token into a BiOp
-            let tok = &self.tokens[self.current_idx - 1];
-            let op = match tok.token_type {
-                TokenType::DoubleEqual => BiOp {
-                    ty: BiOpType::DoubleEqual,
-                    line: tok.line,
-                    column: tok.column,
-                },
-                TokenType::NotEqual => BiOp {
-                    ty: BiOpType::NotEqual,
-                    line: tok.line,
-                    column: tok.column,
-                },
-                _ => panic!("In equality(): op token_type was not == or !=, error probably in check_advance() or equality()"),
-            };
             let right = self.comparison()?;
             ex = Expr::Binary(Box::new(ex), op, Box::new(right));
         }
         Ok(ex)
     }
 
-    // comparison -> term ((">"|">="|"<"|">=") term)*
+    // comparison -> term (("=="|"!="|">"|">="|"<"|">="|"in"|"not" "in"|"is" "not"?) term)*
+    // a chain like a < b < c becomes one Compare expression, a single comparison stays a Binary
     fn comparison(&mut self) -> Result<Expr, PyError> {
-        let mut ex = self.term()?;
-        while self.check_advance(vec![
-            TokenType::Greater,
-            TokenType::GreaterEqual,
-            TokenType::Less,
-            TokenType::LessEqual,
-        ]) {
-            // turn the token into a BiOp
-            let tok = &self.tokens[self.current_idx - 1];
-            let op = match tok.token_type {
-                TokenType::Greater => BiOp {
-                    ty: BiOpType::Greater,
-                    line: tok.line,
-                    column: tok.column,
-                },
-                TokenType::GreaterEqual => BiOp {
-                    ty: BiOpType::GreaterEqual,
-                    line: tok.line,
-                    column: tok.column,
-                },
-                TokenType::Less => BiOp {
-                    ty: BiOpType::Less,
-                    line: tok.line,
-                    column: tok.column,
-                },
-                TokenType::LessEqual => BiOp {
-                    ty: BiOpType::LessEqual,
-                    line: tok.line,
-                    column: tok.column,
-                },
-                _ => panic!("In comparison(): op token_type was not <,<=,> or >=, error probably in check_advance() or comparison()"),
+        let first = self.term()?;
+        let mut rest = Vec::new();
+        loop {
+            // not in consists of two tokens, and not on its own is a unary operator
+            let not_in = self.check_type(vec![TokenType::Not])
+                && self.tokens.get(self.current_idx + 1).is_some_and(|t| t.token_type == TokenType::In);
+            if not_in {
+                self.current_idx += 1;
+            }
+            if !not_in && !self.check_type(vec![
+                TokenType::DoubleEqual,
+                TokenType::NotEqual,
+                TokenType::Greater,
+                TokenType::GreaterEqual,
+                TokenType::Less,
+                TokenType::LessEqual,
+                TokenType::In,
+                TokenType::Is,
+            ]) {
+                break;
+            }
+            self.current_idx += 1;
+            // turn the token into a BiOp, not in points at the not
+            let tok = &self.tokens[self.current_idx - if not_in { 2 } else { 1 }];
+            let (line, column, start) = (tok.line, tok.column, tok.span);
+            let ty = match self.tokens[self.current_idx - 1].token_type.clone() {
+                TokenType::DoubleEqual => BiOpType::DoubleEqual,
+                TokenType::NotEqual => BiOpType::NotEqual,
+                TokenType::Greater => BiOpType::Greater,
+                TokenType::GreaterEqual => BiOpType::GreaterEqual,
+                TokenType::Less => BiOpType::Less,
+                TokenType::LessEqual => BiOpType::LessEqual,
+                TokenType::In if not_in => BiOpType::NotIn,
+                TokenType::In => BiOpType::In,
+                TokenType::Is if self.check_advance(vec![TokenType::Not]) => BiOpType::IsNot,
+                TokenType::Is => BiOpType::Is,
+                _ => panic!("In comparison(): op token_type was not a comparison operator, error probably in comparison()"),
             };
-            let right = self.term()?;
-            ex = Expr::Binary(Box::new(ex), op, Box::new(right));
+            // is not only reaches its not now
+            let op = BiOp { ty, line, column, span: start.to(self.previous_span()) };
+            rest.push((op, self.term()?));
         }
-        Ok(ex)
+        if rest.len() == 1 {
+            let (op, right) = rest.pop().expect("This should never fail, because rest has one element");
+            return Ok(Expr::Binary(Box::new(first), op, Box::new(right)));
+        }
+        if rest.is_empty() {
+            return Ok(first);
+        }
+        Ok(Expr::Compare(Box::new(first), rest))
     }
 
     // term -> factor (("+"|"-") factor)*
@@ -352,11 +418,13 @@ impl Parser {
                     ty: BiOpType::Plus,
                     line: tok.line,
                     column: tok.column,
+                    span: tok.span,
                 },
                 TokenType::Minus => BiOp {
                     ty: BiOpType::Minus,
                     line: tok.line,
                     column: tok.column,
+                    span: tok.span,
                 },
                 _ => panic!("In term(): op token_type was not + or -, error probably in check_advance() or term()"),
             };
@@ -377,11 +445,13 @@ impl Parser {
                     ty: BiOpType::Times,
                     line: tok.line,
                     column: tok.column,
+                    span: tok.span,
                 },
                 TokenType::Slash => BiOp {
                     ty: BiOpType::Divided,
                     line: tok.line,
                     column: tok.column,
+                    span: tok.span,
                 },
                 _ => panic!("In factor(): op token_type was not * or /, error probably in check_advance() or factor()"),
             };
@@ -391,7 +461,7 @@ impl Parser {
         Ok(ex)
     }
 
-    // unary -> ("-"|"not") unary | primary
+    // unary -> ("-"|"not") unary | call
     fn unary(&mut self) -> Result<Expr, PyError> {
         if self.check_advance(vec![TokenType::Minus, TokenType::Not]) {
             // turn the token into a UnOp
@@ -401,40 +471,75 @@ impl Parser {
                     ty: UnOpType::Minus,
                     line: tok.line,
                     column: tok.column,
+                    span: tok.span,
                 },
                 TokenType::Not => UnOp {
                     ty: UnOpType::Not,
                     line: tok.line,
                     column: tok.column,
+                    span: tok.span,
                 },
                 _ => panic!("In unary(): op token_type was not - or not, error probably in check_advance() or unary()"),
             };
-            let right = self.unary()?;
+            let right = self.nested(MAX_NESTING, "SyntaxError: too many nested unary operators", Self::unary)?;
             return Ok(Expr::Unary(op, Box::new(right)));
         }
-        self.primary()
+        self.call()
+    }
+
+    // call -> primary ("[" subscript "]" | "." IDENTIFIER ("(" arguments? ")")?)*
+    fn call(&mut self) -> Result<Expr, PyError> {
+        let mut ex = self.primary()?;
+        loop {
+            if self.check_advance(vec![TokenType::LeftBracket]) {
+                let loc = self.previous_location();
+                let idx = self.subscript()?;
+                self.check_or_error(vec![TokenType::RightBracket], "SyntaxError: Expected closing brackets, found missing or unexpected token".to_owned())?;
+                let span = ex.span().to(self.previous_span());
+                ex = Expr::ListAccess(Box::new(ex), Box::new(idx), loc, span);
+            } else if self.check_advance(vec![TokenType::Point]) {
+                let name = self.identifier("SyntaxError: expected name of attribute after .")?;
+                if self.check_advance(vec![TokenType::LeftParen]) {
+                    let (args, kwargs) = self.func_arguments()?;
+                    let span = ex.span().to(self.previous_span());
+                    ex = Expr::MethodCall(Box::new(ex), name, args, kwargs, span);
+                } else {
+                    ex = Expr::Attribute(Box::new(ex), name);
+                }
+            } else {
+                break;
+            }
+        }
+        Ok(ex)
     }
 
-    // primary -> NUMBER | STRING | "True" | "False" | "None" | "[" arguments? "]" | "(" expr ")" | IDENTIFIER ("(" arguments? ")" | "[" NUMBER "]")?
+    // subscript -> expr | expr? ":" expr? (":" expr?)?
+    fn subscript(&mut self) -> Result<Expr, PyError> {
+        let first = self.current_idx;
+        let start = if self.check_type(vec![TokenType::Colon]) { None } else { Some(self.expression()?) };
+        if !self.check_advance(vec![TokenType::Colon]) {
+            return Ok(start.expect("This should never fail, because without a colon there has to be an expression"));
+        }
+        let stop = if self.check_type(vec![TokenType::Colon, TokenType::RightBracket]) { None } else { Some(Box::new(self.expression()?)) };
+        let step = if self.check_advance(vec![TokenType::Colon]) && !self.check_type(vec![TokenType::RightBracket]) {
+            Some(Box::new(self.expression()?))
+        } else {
+            None
+        };
+        Ok(Expr::Slice(start.map(Box::new), stop, step, self.span_from(first)))
+    }
+
+    // primary -> NUMBER | STRING | FSTRING | "True" | "False" | "None" | "[" arguments? "]" | "(" expr ")" | IDENTIFIER ("(" arguments? ")")?
     fn primary(&mut self) -> Result<Expr, PyError> {
         if self.check_advance(vec![TokenType::Identifier("".to_owned())]) {
             if self.check_advance(vec![TokenType::LeftParen]) {
                 // save the id token to later get the line and column from it
                 let id_tok = self.tokens[self.current_idx - 2].clone();
-                let args = self.func_arguments()?;
-
-                if let TokenType::Identifier(n) = id_tok.token_type {
-                    return Ok(Expr::Call(Name { name: n , line: id_tok.line, column: id_tok.column }, args));
-                } else {
-                    panic!("expected Identifier token here");
-                }
-            } else if self.check_advance(vec![TokenType::LeftBracket]) {
-                let id_tok = self.tokens[self.current_idx - 2].clone();
-                let idx = self.expression()?;
-                self.check_or_error(vec![TokenType::RightBracket], "SyntaxError: Expected closing brackets, found missing or unexpected token".to_owned())?;
+                let (args, kwargs) = self.func_arguments()?;
 
                 if let TokenType::Identifier(n) = id_tok.token_type {
-                        return Ok(Expr::ListAccess(Name { name: n, line: id_tok.line, column: id_tok.column },Box::new(idx) ));
+                    let span = id_tok.span.to(self.previous_span());
+                    return Ok(Expr::Call(Name { name: n , line: id_tok.line, column: id_tok.column, span: id_tok.span }, args, kwargs, span));
                 } else {
                     panic!("expected Identifier token here");
                 }
@@ -452,37 +557,47 @@ impl Parser {
         ]) {
             let previous_tok = &self.tokens[self.current_idx - 1];
             match &previous_tok.token_type {
-                TokenType::Identifier(n) => return Ok(Expr::Variable(Name { name: n.to_owned(), line: previous_tok.line, column: previous_tok.column})),
-                TokenType::String(s) => return Ok(Expr::Literal(Lit::String(s.to_owned()))),
-                TokenType::Int(n) => return Ok(Expr::Literal(Lit::Int(*n))),
-                TokenType::Float(n) => return Ok(Expr::Literal(Lit::Float(*n))),
+                TokenType::Identifier(n) => return Ok(Expr::Variable(Name { name: n.to_owned(), line: previous_tok.line, column: previous_tok.column, span: previous_tok.span})),
+                TokenType::String(s) => return Ok(Expr::Literal(Lit::String(s.to_owned()), previous_tok.span)),
+                TokenType::Int(n) => return Ok(Expr::Literal(Lit::Int(*n), previous_tok.span)),
+                TokenType::Float(n) => return Ok(Expr::Literal(Lit::Float(*n), previous_tok.span)),
                 _ => panic!("In primary(): op token_type was not String or Int or Float, error probably in check_advance() or primary()"),
             }
         }
 
+        if self.check_advance(vec![TokenType::FString(Vec::new())]) {
+            if let TokenType::FString(segments) = self.tokens[self.current_idx - 1].token_type.clone() {
+                return Ok(Expr::FString(self.fstring_parts(segments)?, self.previous_span()));
+            } else {
+                panic!("expected FString token here");
+            }
+        }
+
         if self.check_advance(vec![TokenType::True]) {
-            return Ok(Expr::Literal(Lit::True));
+            return Ok(Expr::Literal(Lit::True, self.previous_span()));
         }
         if self.check_advance(vec![TokenType::False]) {
-            return Ok(Expr::Literal(Lit::False));
+            return Ok(Expr::Literal(Lit::False, self.previous_span()));
         }
         if self.check_advance(vec![TokenType::None]) {
-            return Ok(Expr::Literal(Lit::None));
+            return Ok(Expr::Literal(Lit::None, self.previous_span()));
         }
 
         if self.check_advance(vec![TokenType::LeftBracket]) {
+            let first = self.current_idx - 1;
             let elems = self.list_arguments()?;
-            return Ok(Expr::Literal(Lit::List(elems)));
+            return Ok(Expr::Literal(Lit::List(elems), self.span_from(first)));
         }
 
         if self.check_advance(vec![TokenType::LeftParen]) {
+            let first = self.current_idx - 1;
             let ex = self.expression()?;
             self.check_or_error(vec![TokenType::RightParen], "SyntaxError: Missing closing parentheses".to_owned())?;
-            return Ok(Expr::Grouping(Box::new(ex)));
+            return Ok(Expr::Grouping(Box::new(ex), self.span_from(first)));
         }
 
-        // TODO: really necessary??
-        if self.check_advance(vec![TokenType::Indent, TokenType::Dedent]) {
+        // don't consume the indent, so synchronize() can skip the whole unexpectedly indented block
+        if self.check_type(vec![TokenType::Indent, TokenType::Dedent]) {
             return Err(PyError {
                 msg: "IndentationError: unexpected indent/dedent".to_owned(),
                 line: self.tokens[self.current_idx].line,
@@ -496,11 +611,26 @@ impl Parser {
         })
     }
 
-    // arguments -> expr ("," expr)*
-    fn func_arguments(&mut self) -> Result<Vec<Expr>, PyError> {
+    // arguments -> argument ("," argument)*
+    // argument  -> (IDENTIFIER "=")? expr
+    fn func_arguments(&mut self) -> Result<Arguments, PyError> {
         let mut args = Vec::new();
+        let mut kwargs = Vec::new();
         while !self.check_advance(vec![TokenType::RightParen]) {
-            args.push(self.expression()?);
+            let is_keyword = matches!(self.tokens[self.current_idx].token_type, TokenType::Identifier(_))
+                && self.tokens.get(self.current_idx + 1).is_some_and(|t| t.token_type == TokenType::Equal);
+            if is_keyword {
+                let id_tok = &self.tokens[self.current_idx];
+                let name = Name { name: id_tok.value.clone(), line: id_tok.line, column: id_tok.column, span: id_tok.span };
+                self.current_idx += 2;
+                kwargs.push((name, self.expression()?));
+            } else {
+                let ex = self.expression()?;
+                if !kwargs.is_empty() {
+                    return Err(self.error_at_previous("SyntaxError: positional argument follows keyword argument"));
+                }
+                args.push(ex);
+            }
             if self.check_advance(vec![TokenType::Comma]) {
                 // NOTE: this allows e.g. f(1,), but python allows it too so no matter
                 continue;
@@ -511,7 +641,7 @@ impl Parser {
                 }
             }
         }
-        Ok(args)
+        Ok((args, kwargs))
     }
 
     fn list_arguments(&mut self) -> Result<Vec<Expr>, PyError> {
@@ -531,16 +661,97 @@ impl Parser {
         Ok(args)
     }
 
+    // parses the expressions inside the replacement fields of an f-string, the scanner already turned them into tokens
+    fn fstring_parts(&self, segments: Vec<FStringSegment>) -> Result<Vec<FStringPart>, PyError> {
+        let mut parts = Vec::new();
+        for seg in segments {
+            match seg {
+                FStringSegment::Literal(s) => parts.push(FStringPart::Literal(s)),
+                FStringSegment::Field { tokens, conversion, spec } => {
+                    let loc = Location { line: tokens[0].line, column: tokens[0].column, span: tokens[0].span };
+                    let mut p = Parser { tokens, current_idx: 0, errors: Vec::new(), depth: self.depth };
+                    let ex = p.expression()?;
+                    p.check_or_error(vec![TokenType::EndOfFile], "SyntaxError: f-string: expecting '}'".to_owned())?;
+                    let spec = self.fstring_parts(spec)?;
+                    parts.push(FStringPart::Field(Box::new(ex), conversion, spec, loc));
+                }
+            }
+        }
+        Ok(parts)
+    }
+
     //////////////////////
     // helper functions //
     //////////////////////
 
+    // panic-mode error recovery: skips tokens until the start of the next statement
+    // skipping only to the next line is not enough for statements spanning multiple lines (if, while, def),
+    // so a block belonging to the broken line is still parsed for its own errors, but its statements are thrown away
+    fn synchronize(&mut self) {
+        loop {
+            match self.tokens[self.current_idx].token_type {
+                // a dedent closes the block the broken statement is in, so it has to be left for block()
+                TokenType::EndOfFile | TokenType::Dedent => return,
+                TokenType::EndOfLine => {
+                    self.current_idx += 1;
+                    if !self.check_type(vec![TokenType::Indent]) {
+                        return;
+                    }
+                }
+                TokenType::Indent => {
+                    self.current_idx += 1;
+                    // a block that is too deep has already been reported by block()
+                    if self.depth >= MAX_INDENT {
+                        self.skip_block();
+                    } else {
+                        self.block_body();
+                    }
+                    // the else block of a broken if statement belongs to it as well
+                    if !self.check_type(vec![TokenType::Else]) {
+                        return;
+                    }
+                }
+                _ => self.current_idx += 1,
+            }
+        }
+    }
+
+    // parses one level deeper, msg is the error if that is deeper than max
+    fn nested<T>(&mut self, max: usize, msg: &str, rule: impl FnOnce(&mut Self) -> Result<T, PyError>) -> Result<T, PyError> {
+        if self.depth >= max {
+            return Err(PyError {
+                msg: msg.to_owned(),
+                line: self.tokens[self.current_idx].line,
+                column: self.tokens[self.current_idx].column,
+            });
+        }
+        self.depth += 1;
+        let res = rule(self);
+        self.depth -= 1;
+        res
+    }
+
+    // skips to after the dedent that closes the block, without parsing it
+    fn skip_block(&mut self) {
+        let mut open = 1;
+        while open > 0 {
+            match self.tokens[self.current_idx].token_type {
+                TokenType::EndOfFile => return,
+                TokenType::Indent => open += 1,
+                TokenType::Dedent => open -= 1,
+                _ => (),
+            }
+            self.current_idx += 1;
+        }
+    }
+
     // checks if current token has one of the types
     fn check_type(&self, types: Vec<TokenType>) -> bool {
         // not very pretty, but needed some way to eliminate value inside literal types
         match &self.tokens[self.current_idx].token_type {
             TokenType::Identifier(_) => types.iter().any(|t| matches!(t, TokenType::Identifier(_))),
             TokenType::String(_) => types.iter().any(|t| matches!(t, TokenType::String(_))),
+            TokenType::FString(_) => types.iter().any(|t| matches!(t, TokenType::FString(_))),
             TokenType::Int(_) => types.iter().any(|t| matches!(t, TokenType::Int(_))),
             TokenType::Float(_) => types.iter().any(|t| matches!(t, TokenType::Float(_))),
             other => types.contains(other),
@@ -556,6 +767,31 @@ impl Parser {
         false
     }
 
+    // span of the token before the current one
+    fn previous_span(&self) -> Span {
+        self.tokens[self.current_idx - 1].span
+    }
+
+    // from the token at idx to the one before the current one
+    fn span_from(&self, idx: usize) -> Span {
+        self.tokens[idx].span.to(self.previous_span())
+    }
+
+    fn previous_location(&self) -> Location {
+        let tok = &self.tokens[self.current_idx - 1];
+        Location { line: tok.line, column: tok.column, span: tok.span }
+    }
+
+    // error pointing at the token before the current one
+    fn error_at_previous(&self, msg: &str) -> PyError {
+        let tok = &self.tokens[self.current_idx - 1];
+        PyError {
+            msg: msg.to_owned(),
+            line: tok.line,
+            column: tok.column,
+        }
+    }
+
     // check_advance but outputs error on False
     fn check_or_error(&mut self, types: Vec<TokenType>, msg: String) -> Result<(), PyError> {
         if !self.check_advance(types) {
@@ -0,0 +1,303 @@
+// works out where each variable lives before the code runs
+// like in python, the variables of a function are its parameters and the names it assigns,
+// other names belong to an enclosing function (then shared through a cell) or to the module
+
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::{
+    builtins,
+    common::{ast::*, py_error::PyError},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    // slot of a variable only used by its own function
+    Local(usize),
+    // cell of the running function, for variables shared with nested functions
+    Cell(usize),
+    // module table, then builtins
+    Global,
+}
+
+// the scopes of the functions defined by some code, by where the name in their def starts in the source,
+// so the tree-walker works them out once and not every time a def runs
+pub type Functions = HashMap<usize, Rc<FunctionScope>>;
+
+#[derive(Debug)]
+pub struct FunctionScope {
+    // the variables that aren't shared, in slot order
+    pub slots: Vec<String>,
+    // the own variables shared with nested functions, then the ones of enclosing functions
+    pub cells: Vec<String>,
+    pub own_cells: usize,
+    // the slots and cells by name, every other name is global
+    vars: HashMap<String, Access>,
+    // the functions defined in this one
+    pub nested: Functions,
+    // the variables of this and the enclosing functions, innermost last
+    visible: Vec<HashSet<String>>,
+}
+
+impl FunctionScope {
+    // enclosing is None for functions defined at the top level of a module
+    pub fn new(parameters: &[Name], body: &[Stmt], enclosing: Option<&FunctionScope>) -> Self {
+        Self::analyze(parameters, body, enclosing.map_or(&[], |e| &e.visible))
+    }
+
+    // outer are the variables of the enclosing functions
+    fn analyze(parameters: &[Name], body: &[Stmt], outer: &[HashSet<String>]) -> Self {
+        let mut locals: Vec<String> = Vec::new();
+        for p in parameters {
+            add(&mut locals, &p.name);
+        }
+        bound_names(body, &mut locals);
+        let own: HashSet<String> = locals.iter().cloned().collect();
+        let mut visible = outer.to_vec();
+        visible.push(own.clone());
+
+        let mut reads = Vec::new();
+        let mut defs = Vec::new();
+        used_names(body, &mut reads, &mut defs);
+        let in_outer = |name: &str| outer.iter().any(|vars| vars.contains(name));
+        let mut captured = HashSet::new();
+        let mut free = Vec::new();
+        let mut nested_scopes = HashMap::new();
+        for name in reads {
+            if !own.contains(&name.name) && in_outer(&name.name) {
+                add(&mut free, &name.name);
+            }
+        }
+        // whatever nested functions take from outside of them has to be shared by this function
+        for (def_name, params, def_body) in defs {
+            let nested = FunctionScope::analyze(params, def_body, &visible);
+            for name in nested.free() {
+                if own.contains(name) {
+                    captured.insert(name.clone());
+                } else {
+                    add(&mut free, name);
+                }
+            }
+            nested_scopes.insert(def_name.span.start, Rc::new(nested));
+        }
+
+        let (mut cells, slots): (Vec<String>, Vec<String>) =
+            locals.into_iter().partition(|v| captured.contains(v));
+        let own_cells = cells.len();
+        cells.extend(free);
+        let vars = slots
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (v.clone(), Access::Local(i)))
+            .chain(
+                cells
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (v.clone(), Access::Cell(i))),
+            )
+            .collect();
+        FunctionScope {
+            slots,
+            cells,
+            own_cells,
+            vars,
+            nested: nested_scopes,
+            visible,
+        }
+    }
+
+    pub fn access(&self, name: &str) -> Access {
+        self.vars.get(name).copied().unwrap_or(Access::Global)
+    }
+
+    // the variables taken from enclosing functions, in the order their cells are passed to the function
+    pub fn free(&self) -> &[String] {
+        &self.cells[self.own_cells..]
+    }
+}
+
+// the scopes of the functions defined at the top level of a module
+pub fn module_functions(stmts: &[Stmt]) -> Functions {
+    let mut defs = Vec::new();
+    used_names(stmts, &mut Vec::new(), &mut defs);
+    defs.into_iter()
+        .map(|(name, params, body)| {
+            let scope = FunctionScope::new(params, body, None);
+            (name.span.start, Rc::new(scope))
+        })
+        .collect()
+}
+
+// at the top level of a module every variable is global
+pub fn access(scope: Option<&FunctionScope>, name: &str) -> Access {
+    scope.map_or(Access::Global, |s| s.access(name))
+}
+
+// the names that can't be defined when they are used, because nothing ever assigns them, in the order they appear
+// only reported by --check, since code that reads them might never run
+pub fn undefined_names(stmts: &[Stmt]) -> Vec<PyError> {
+    let mut globals = Vec::new();
+    bound_names(stmts, &mut globals);
+    let mut errors = Vec::new();
+    check_scope(stmts, None, &globals, &mut errors);
+    errors.sort_by_key(|e| (e.line, e.column));
+    errors
+}
+
+fn check_scope(
+    body: &[Stmt],
+    scope: Option<&FunctionScope>,
+    globals: &[String],
+    errors: &mut Vec<PyError>,
+) {
+    let mut reads = Vec::new();
+    let mut defs = Vec::new();
+    used_names(body, &mut reads, &mut defs);
+    for name in reads {
+        if access(scope, &name.name) == Access::Global
+            && !globals.contains(&name.name)
+            && builtins::lookup(&name.name).is_none()
+        {
+            errors.push(PyError {
+                msg: format!("NameError: name {} is not defined", name.name),
+                line: name.line,
+                column: name.column,
+            });
+        }
+    }
+    for (_, params, def_body) in defs {
+        let nested = FunctionScope::new(params, def_body, scope);
+        check_scope(def_body, Some(&nested), globals, errors);
+    }
+}
+
+fn add(names: &mut Vec<String>, name: &str) {
+    if !names.iter().any(|n| n == name) {
+        names.push(name.to_owned());
+    }
+}
+
+// the names assigned by the statements, not looking into the bodies of functions
+pub fn bound_names(stmts: &[Stmt], names: &mut Vec<String>) {
+    for st in stmts {
+        match st {
+            Stmt::AssignVar(n, _) | Stmt::FunDecl(_, n, ..) => add(names, &n.name),
+            Stmt::If(_, _, t, e) => {
+                bound_names(t, names);
+                if let Some(e) = e {
+                    bound_names(e, names);
+                }
+            }
+            Stmt::While(_, _, b) => bound_names(b, names),
+            Stmt::With(_, _, alias, b) => {
+                if let Some(alias) = alias {
+                    add(names, &alias.name);
+                }
+                bound_names(b, names);
+            }
+            Stmt::Import(_, imported) | Stmt::FromImport(_, _, imported) => {
+                for (name, alias) in imported {
+                    add(names, &alias.as_ref().unwrap_or(name).name);
+                }
+            }
+            Stmt::Expr(_) | Stmt::AssignList(..) | Stmt::Return(..) => (),
+        }
+    }
+}
+
+// the variables read by the statements and the functions they define, not looking into the bodies of those
+pub fn used_names<'a>(
+    stmts: &'a [Stmt],
+    reads: &mut Vec<&'a Name>,
+    defs: &mut Vec<(&'a Name, &'a [Name], &'a [Stmt])>,
+) {
+    for st in stmts {
+        match st {
+            Stmt::Expr(e) | Stmt::AssignVar(_, e) => expr_names(e, reads),
+            Stmt::AssignList(n, i, e) => {
+                expr_names(i, reads);
+                reads.push(n);
+                expr_names(e, reads);
+            }
+            Stmt::If(_, c, t, e) => {
+                expr_names(c, reads);
+                used_names(t, reads, defs);
+                if let Some(e) = e {
+                    used_names(e, reads, defs);
+                }
+            }
+            Stmt::While(_, c, b) => {
+                expr_names(c, reads);
+                used_names(b, reads, defs);
+            }
+            Stmt::With(_, e, _, b) => {
+                expr_names(e, reads);
+                used_names(b, reads, defs);
+            }
+            Stmt::FunDecl(_, n, p, b) => defs.push((n, p, b.as_slice())),
+            Stmt::Return(_, e) => {
+                if let Some(e) = e {
+                    expr_names(e, reads);
+                }
+            }
+            Stmt::Import(..) | Stmt::FromImport(..) => (),
+        }
+    }
+}
+
+fn expr_names<'a>(expr: &'a Expr, reads: &mut Vec<&'a Name>) {
+    match expr {
+        Expr::Unary(_, e) | Expr::Grouping(e, _) | Expr::Attribute(e, _) => expr_names(e, reads),
+        Expr::Binary(e1, _, e2) | Expr::ListAccess(e1, e2, ..) => {
+            expr_names(e1, reads);
+            expr_names(e2, reads);
+        }
+        Expr::Compare(e, rest) => {
+            expr_names(e, reads);
+            for (_, e) in rest {
+                expr_names(e, reads);
+            }
+        }
+        Expr::Literal(Lit::List(elems), _) => {
+            for e in elems {
+                expr_names(e, reads);
+            }
+        }
+        Expr::Literal(..) => (),
+        Expr::Variable(n) => reads.push(n),
+        Expr::Call(n, args, keywords, _) => {
+            reads.push(n);
+            args_names(args, keywords, reads);
+        }
+        Expr::MethodCall(o, _, args, keywords, _) => {
+            expr_names(o, reads);
+            args_names(args, keywords, reads);
+        }
+        Expr::FString(parts, _) => fstring_names(parts, reads),
+        Expr::Slice(start, stop, step, _) => {
+            for e in [start, stop, step].into_iter().flatten() {
+                expr_names(e, reads);
+            }
+        }
+    }
+}
+
+fn args_names<'a>(args: &'a [Expr], keywords: &'a [(Name, Expr)], reads: &mut Vec<&'a Name>) {
+    for a in args {
+        expr_names(a, reads);
+    }
+    for (_, e) in keywords {
+        expr_names(e, reads);
+    }
+}
+
+fn fstring_names<'a>(parts: &'a [FStringPart], reads: &mut Vec<&'a Name>) {
+    for part in parts {
+        if let FStringPart::Field(e, _, spec, _) = part {
+            expr_names(e, reads);
+            fstring_names(spec, reads);
+        }
+    }
+}
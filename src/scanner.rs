@@ -1,19 +1,28 @@
-use std::cmp::Ordering;
+use std::{cmp::Ordering, iter::Peekable};
 
-use crate::common::{py_error::*, token::*};
+use crate::common::{ast::Span, py_error::*, token::*};
 
 pub fn scan(code: String) -> Option<Vec<Token>> {
+    scan_with_trivia(code).map(|(tokens, _)| tokens)
+}
+
+// also returns the comments and blank lines, in the order they appear
+// the spans of the tokens are byte offsets into the code
+pub fn scan_with_trivia(code: String) -> Option<(Vec<Token>, Vec<Trivia>)> {
     let mut tokens: Vec<Token> = Vec::new();
+    let mut trivia = Vec::new();
     // error flag to decide whether to return Some or None
     let mut error = false;
     let mut indent_stack = vec![0];
+    // open brackets, inside of them newlines and indentation don't count like in python
+    let mut nesting = 0usize;
     let mut current_idx = 0;
     let mut line = 1;
     let mut column = 1;
 
     while current_idx < code.chars().count() {
         // calc the indent at every line start
-        if column == 1 {
+        if column == 1 && nesting == 0 {
             match scan_indent(
                 &code,
                 &mut tokens,
@@ -30,25 +39,55 @@ pub fn scan(code: String) -> Option<Vec<Token>> {
                     break; // after indentation error, scanner should stop, otherwise every new correct dedent is an error since the stack is gone
                 }
             }
+            if code.chars().nth(current_idx) == Some('\n') {
+                trivia.push(Trivia::BlankLine(line));
+            }
         }
-        match scan_token(&code, &mut current_idx, line, &mut column) {
+        let start = current_idx;
+        match scan_token(&code, &mut current_idx, &mut line, &mut column) {
             Ok(x) => match x {
                 // add token
-                Some(t) => {
-                    // special case because idx and col get updated inside function (to circumvent weird trimming of trailing zeros by rust)
-                    if let TokenType::Float(_) = t.token_type {
-                    } else {
-                        // move the index by the lexeme length
-                        current_idx += t.value.chars().count();
-                        // increase column counter by same amount
-                        column += t.value.chars().count() as u64;
-                    }
-                    // if it's a EoL token increase the line counter and reset column counter
-                    if let TokenType::EndOfLine = t.token_type {
-                        line += 1;
-                        column = 1;
+                Some(mut t) => {
+                    advance_past(&t, &mut current_idx, &mut line, &mut column);
+                    t.span = Span {
+                        start,
+                        end: current_idx,
+                    };
+                    // whether there is code on the line before the token
+                    let after_code = tokens
+                        .last()
+                        .is_some_and(|l| l.token_type != TokenType::EndOfLine);
+                    match &t.token_type {
+                        TokenType::Comment(text) => {
+                            // the blocks the comment is indented into
+                            let depth = indent_stack
+                                .iter()
+                                .skip(1)
+                                .filter(|&&i| i < t.column)
+                                .count();
+                            trivia.push(Trivia::Comment {
+                                text: text.clone(),
+                                line: t.line,
+                                own_line: (!after_code).then_some(depth),
+                            });
+                        }
+                        // lines without code, e.g. only a comment, don't end a statement,
+                        // otherwise a comment at the start of a block would come before its indent
+                        TokenType::EndOfLine if nesting > 0 || !after_code => (),
+                        TokenType::LeftParen | TokenType::LeftBracket => {
+                            nesting += 1;
+                            tokens.push(t);
+                        }
+                        TokenType::RightParen | TokenType::RightBracket => {
+                            nesting = nesting.saturating_sub(1);
+                            tokens.push(t);
+                        }
+                        TokenType::String(_) if triple_quoted(&code, start) => {
+                            trivia.push(Trivia::TripleQuoted(start));
+                            tokens.push(t);
+                        }
+                        _ => tokens.push(t),
                     }
-                    tokens.push(t)
                 }
                 // ignore
                 None => {
@@ -70,12 +109,21 @@ pub fn scan(code: String) -> Option<Vec<Token>> {
     // push Dedent on stack for every Indent above 0
     while let Some(i) = indent_stack.pop() {
         if i > 0 {
-            tokens.push(Token::create(TokenType::Dedent, line, column));
+            tokens.push(empty_token(TokenType::Dedent, current_idx, line, column));
         }
     }
-    tokens.push(Token::create(TokenType::EndOfFile, line, column));
+    tokens.push(empty_token(TokenType::EndOfFile, current_idx, line, column));
     if !error {
-        Some(tokens)
+        // the scanner counts chars, the spans are in bytes
+        let mut offsets: Vec<usize> = code.char_indices().map(|(i, _)| i).collect();
+        offsets.push(code.len());
+        to_bytes(&mut tokens, &offsets);
+        for t in &mut trivia {
+            if let Trivia::TripleQuoted(start) = t {
+                *start = offsets[*start];
+            }
+        }
+        Some((tokens, trivia))
     } else {
         None
     }
@@ -121,7 +169,7 @@ fn scan_indent(
                 break;
             }
             if let Some(i) = indent_stack.pop() {
-                tokens.push(Token::create(TokenType::Dedent, line, *column));
+                tokens.push(empty_token(TokenType::Dedent, *current_idx, line, *column));
                 current_indent = i;
             } else {
                 // if there was no matching indent found, return an error
@@ -140,7 +188,7 @@ fn scan_indent(
         Ordering::Greater => {
             indent_stack.push(current_indent);
             indent_stack.push(line_indent);
-            tokens.push(Token::create(TokenType::Indent, line, *column));
+            tokens.push(empty_token(TokenType::Indent, *current_idx, line, *column));
         }
     }
 
@@ -150,7 +198,7 @@ fn scan_indent(
 fn scan_token(
     code: &str,
     current_idx: &mut usize,
-    line: u64,
+    line: &mut u64,
     column: &mut u64,
 ) -> Result<Option<Token>, PyError> {
     let mut code = code.chars();
@@ -159,121 +207,571 @@ fn scan_token(
         .expect("This should not fail, since current_idx should not be out of bounds here");
     match current_char {
         // single character
-        '+' => Ok(Some(Token::create(TokenType::Plus, line, *column))),
-        '-' => Ok(Some(Token::create(TokenType::Minus, line, *column))),
-        '*' => Ok(Some(Token::create(TokenType::Asterisk, line, *column))),
-        '/' => Ok(Some(Token::create(TokenType::Slash, line, *column))),
-        ':' => Ok(Some(Token::create(TokenType::Colon, line, *column))),
-        '(' => Ok(Some(Token::create(TokenType::LeftParen, line, *column))),
-        ')' => Ok(Some(Token::create(TokenType::RightParen, line, *column))),
-        '[' => Ok(Some(Token::create(TokenType::LeftBracket, line, *column))),
-        ']' => Ok(Some(Token::create(TokenType::RightBracket, line, *column))),
-        ',' => Ok(Some(Token::create(TokenType::Comma, line, *column))),
-        '\n' => Ok(Some(Token::create(TokenType::EndOfLine, line, *column))),
+        '+' => Ok(Some(Token::create(TokenType::Plus, *line, *column))),
+        '-' => Ok(Some(Token::create(TokenType::Minus, *line, *column))),
+        '*' => Ok(Some(Token::create(TokenType::Asterisk, *line, *column))),
+        '/' => Ok(Some(Token::create(TokenType::Slash, *line, *column))),
+        ':' => Ok(Some(Token::create(TokenType::Colon, *line, *column))),
+        '(' => Ok(Some(Token::create(TokenType::LeftParen, *line, *column))),
+        ')' => Ok(Some(Token::create(TokenType::RightParen, *line, *column))),
+        '[' => Ok(Some(Token::create(TokenType::LeftBracket, *line, *column))),
+        ']' => Ok(Some(Token::create(TokenType::RightBracket, *line, *column))),
+        ',' => Ok(Some(Token::create(TokenType::Comma, *line, *column))),
+        '.' => Ok(Some(Token::create(TokenType::Point, *line, *column))),
+        '\n' => Ok(Some(Token::create(TokenType::EndOfLine, *line, *column))),
 
         // double character
         '!' => match code.next() {
-            Some('=') => Ok(Some(Token::create(TokenType::NotEqual, line, *column))),
+            Some('=') => Ok(Some(Token::create(TokenType::NotEqual, *line, *column))),
             _ => Err(PyError {
                 msg: format!("SyntaxError: Unknown Token: \"{current_char}\""),
-                line,
+                line: *line,
                 column: *column,
             }),
         },
 
         // single or double character
         '=' => match code.next() {
-            Some('=') => Ok(Some(Token::create(TokenType::DoubleEqual, line, *column))),
-            _ => Ok(Some(Token::create(TokenType::Equal, line, *column))),
+            Some('=') => Ok(Some(Token::create(TokenType::DoubleEqual, *line, *column))),
+            _ => Ok(Some(Token::create(TokenType::Equal, *line, *column))),
         },
         '>' => match code.next() {
-            Some('=') => Ok(Some(Token::create(TokenType::GreaterEqual, line, *column))),
-            _ => Ok(Some(Token::create(TokenType::Greater, line, *column))),
+            Some('=') => Ok(Some(Token::create(TokenType::GreaterEqual, *line, *column))),
+            _ => Ok(Some(Token::create(TokenType::Greater, *line, *column))),
         },
         '<' => match code.next() {
-            Some('=') => Ok(Some(Token::create(TokenType::LessEqual, line, *column))),
-            _ => Ok(Some(Token::create(TokenType::Less, line, *column))),
+            Some('=') => Ok(Some(Token::create(TokenType::LessEqual, *line, *column))),
+            _ => Ok(Some(Token::create(TokenType::Less, *line, *column))),
         },
 
         // literals
-        '"' => build_string(code, current_idx, line, column),
-        '0'..='9' => build_number(code, current_char, current_idx, line, column),
-        '_' | 'a'..='z' | 'A'..='Z' => Ok(Some(build_identifier(code, current_char, line, column))),
+        '"' | '\'' => build_string(code, current_char, false, false, current_idx, line, column),
+        // raw string and f-string prefixes
+        'r' | 'R' | 'f' | 'F' if string_prefix(current_char, code.clone()).is_some() => {
+            let (raw, fmt) = string_prefix(current_char, code.clone())
+                .expect("This should never fail, because the prefix was just checked");
+            // skip the rest of the prefix, the quote is the char after it
+            let quote = code
+                .nth(raw as usize + fmt as usize - 1)
+                .expect("This should never fail, because the quote was just peeked");
+            build_string(code, quote, raw, fmt, current_idx, line, column)
+        }
+        '0'..='9' => build_number(code, current_char, current_idx, *line, column),
+        '_' | 'a'..='z' | 'A'..='Z' => {
+            Ok(Some(build_identifier(code, current_char, *line, column)))
+        }
 
         // ignored
         '\r' | '\t' | ' ' => Ok(None),
         // comments
         '#' => {
-            while code
-                .next()
-                .expect("There should always be a newline between the start of a comment and the end of the iterator/file")
-                != '\n'
-            {
-                *current_idx += 1;
-                *column += 1;
-            }
-            Ok(None)
+            let text = code.take_while(|&c| c != '\n').collect();
+            Ok(Some(Token::create(
+                TokenType::Comment(text),
+                *line,
+                *column,
+            )))
         }
 
         // unknown
         _ => Err(PyError {
             msg: format!("SyntaxError: Unknown Token: {current_char}"),
-            line,
+            line: *line,
             column: *column,
         }),
     }
 }
 
+// keeps track of the position inside a string literal, since it can span multiple lines
+struct LiteralCursor<I: Iterator<Item = char>> {
+    code: Peekable<I>,
+    // index of the first char of the literal, its prefix if it has one
+    start_idx: usize,
+    // how many chars of the code belong to the literal so far, including prefix and opening quote
+    consumed: usize,
+    line: u64,
+    // value of consumed at the last newline inside the literal, needed to calc the column after it
+    last_newline: Option<usize>,
+    start_col: u64,
+}
+
+impl<I: Iterator<Item = char> + Clone> LiteralCursor<I> {
+    fn next(&mut self) -> Option<char> {
+        let c = self.code.next()?;
+        self.consumed += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.last_newline = Some(self.consumed);
+        }
+        Some(c)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.code.peek().copied()
+    }
+
+    // column of the next char
+    fn column(&self) -> u64 {
+        match self.last_newline {
+            Some(n) => (self.consumed - n) as u64 + 1,
+            None => self.start_col + self.consumed as u64,
+        }
+    }
+
+    // checks if the next chars are the given quote (three times for triple quoted strings)
+    fn at_quotes(&self, quote: char, count: usize) -> bool {
+        let mut peek = self.code.clone();
+        (0..count).all(|_| peek.next() == Some(quote))
+    }
+}
+
+// handles all string literal kinds: '...', "...", triple quoted strings spanning multiple lines, raw strings and f-strings
+// idx, line and col get updated inside this function, because the literal's length in the code differs from its text
 fn build_string(
-    mut code: impl Iterator<Item = char>,
+    code: impl Iterator<Item = char> + Clone,
+    quote: char,
+    raw: bool,
+    fmt: bool,
     current_idx: &mut usize,
-    line: u64,
+    line: &mut u64,
     column: &mut u64,
 ) -> Result<Option<Token>, PyError> {
-    // used for still moving the index in case of error
-    let mut err_idx = *current_idx;
+    let start_line = *line;
+    let start_col = *column;
+    let mut cur = LiteralCursor {
+        code: code.peekable(),
+        start_idx: *current_idx,
+        consumed: 1 + raw as usize + fmt as usize,
+        line: *line,
+        last_newline: None,
+        start_col,
+    };
+
+    let triple = cur.at_quotes(quote, 2);
+    let quote_count = if triple {
+        cur.next();
+        cur.next();
+        3
+    } else {
+        1
+    };
+
+    let unterminated = |text: &str| PyError {
+        msg: format!("SyntaxError: Unterminated String: {quote}{text}"),
+        line: start_line,
+        column: start_col,
+    };
+
+    // errors inside the literal don't stop it immediately, so the rest of it isn't scanned as code
+    let mut error = None;
     let mut text = String::new();
-    while let Some(c) = code.next() {
-        err_idx += 1;
+    // literal parts and replacement fields of f-strings
+    let mut segments = Vec::new();
+    loop {
+        // missing quote at end of a single line string
+        if !triple && cur.peek() == Some('\n') {
+            // -1 so idx points at the newline after the error moved it
+            *current_idx += cur.consumed - 1;
+            return Err(unterminated(&text));
+        }
+        let Some(c) = cur.next() else {
+            // let idx point at the end of the code
+            *current_idx += cur.consumed - 1;
+            return Err(unterminated(&text));
+        };
         match c {
+            // end string
+            c if c == quote && cur.at_quotes(quote, quote_count - 1) => {
+                for _ in 1..quote_count {
+                    cur.next();
+                }
+                break;
+            }
+            '{' | '}' if fmt && cur.peek() == Some(c) => {
+                cur.next();
+                text.push(c);
+            }
+            '{' if fmt => {
+                if !text.is_empty() {
+                    segments.push(FStringSegment::Literal(std::mem::take(&mut text)));
+                }
+                match build_fstring_field(&mut cur, quote, triple) {
+                    Ok(f) => segments.push(f),
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    }
+                }
+            }
+            '}' if fmt => {
+                error.get_or_insert(PyError {
+                    msg: "SyntaxError: f-string: single '}' is not allowed".to_owned(),
+                    line: cur.line,
+                    column: cur.column() - 1,
+                });
+            }
+            // raw strings keep the backslash, but it still prevents the next char from ending the string
+            '\\' if raw => {
+                text.push(c);
+                if let Some(next) = cur.next() {
+                    text.push(next);
+                }
+            }
             '\\' => {
-                *current_idx += 1;
-                err_idx += 1;
-                match code.next() {
-                    Some(c) => match c {
-                        '"' | '\\' => text.push(c),
-                        'n' => text.push('\n'),
-                        _ => {
-                            *current_idx += 1;
-                            continue;
-                        }
-                    },
+                let Some(esc) = cur.next() else {
+                    *current_idx += cur.consumed - 1;
+                    return Err(unterminated(&text));
+                };
+                if let Err(e) = build_escape(&mut cur, esc, &mut text) {
+                    error.get_or_insert(e);
+                }
+            }
+            _ => text.push(c),
+        }
+    }
+
+    *current_idx += cur.consumed;
+    *column = cur.column();
+    *line = cur.line;
+    if let Some(e) = error {
+        // the idx and col get moved by one more after an error
+        *current_idx -= 1;
+        *column -= 1;
+        return Err(e);
+    }
+    let token_type = if fmt {
+        if !text.is_empty() {
+            segments.push(FStringSegment::Literal(text));
+        }
+        TokenType::FString(segments)
+    } else {
+        TokenType::String(text)
+    };
+    Ok(Some(Token::create(token_type, start_line, start_col)))
+}
+
+// pushes the char(s) for the escape sequence starting with esc (the backslash was already consumed)
+fn build_escape<I: Iterator<Item = char> + Clone>(
+    cur: &mut LiteralCursor<I>,
+    esc: char,
+    text: &mut String,
+) -> Result<(), PyError> {
+    match esc {
+        // backslash at the end of a line continues the string on the next one
+        '\n' => (),
+        '\\' | '\'' | '"' => text.push(esc),
+        'n' => text.push('\n'),
+        't' => text.push('\t'),
+        'r' => text.push('\r'),
+        'a' => text.push('\x07'),
+        'b' => text.push('\x08'),
+        'f' => text.push('\x0c'),
+        'v' => text.push('\x0b'),
+        // octal escape with up to three digits, e.g. \0
+        '0'..='7' => {
+            let mut value = esc.to_digit(8).expect("esc is an octal digit here");
+            for _ in 0..2 {
+                match cur.peek().and_then(|d| d.to_digit(8)) {
+                    Some(d) => {
+                        value = value * 8 + d;
+                        cur.next();
+                    }
+                    None => break,
+                }
+            }
+            text.push(char::from_u32(value).expect("octal escapes are at most 0o777"));
+        }
+        'x' | 'u' | 'U' => {
+            let digits = match esc {
+                'x' => 2,
+                'u' => 4,
+                _ => 8,
+            };
+            let mut value: u32 = 0;
+            for _ in 0..digits {
+                match cur.peek().and_then(|d| d.to_digit(16)) {
+                    Some(d) => {
+                        value = value * 16 + d;
+                        cur.next();
+                    }
                     None => {
-                        *current_idx = err_idx - 1;
                         return Err(PyError {
-                            msg: format!("SyntaxError: Unterminated String: \"{text}"),
-                            line,
-                            column: *column,
-                        });
+                            msg: format!(
+                            "SyntaxError: truncated \\{esc} escape, expected {digits} hex digits"
+                        ),
+                            line: cur.line,
+                            column: cur.column(),
+                        })
                     }
                 }
             }
-            // end string
-            '"' => break,
-            // missing " at end
-            '\n' => {
-                // -1 so it points at the newline
-                *current_idx = err_idx - 1;
-                return Err(PyError {
-                    msg: format!("SyntaxError: Unterminated String: \"{text}"),
-                    line,
-                    column: *column,
-                });
+            match char::from_u32(value) {
+                Some(ch) => text.push(ch),
+                None => {
+                    return Err(PyError {
+                        msg: format!("SyntaxError: illegal Unicode character in \\{esc} escape"),
+                        line: cur.line,
+                        column: cur.column(),
+                    })
+                }
             }
-            _ => text.push(c),
         }
+        // unknown escapes are kept as they are
+        _ => {
+            text.push('\\');
+            text.push(esc);
+        }
+    }
+    Ok(())
+}
+
+// scans a replacement field {expr!conversion:spec} of an f-string, the opening { was already consumed
+// the expression is scanned into its own tokens here, so the parser only has to parse them
+fn build_fstring_field<I: Iterator<Item = char> + Clone>(
+    cur: &mut LiteralCursor<I>,
+    quote: char,
+    triple: bool,
+) -> Result<FStringSegment, PyError> {
+    let expr_line = cur.line;
+    let expr_col = cur.column();
+    let expr_idx = cur.start_idx + cur.consumed;
+    let field_error = |msg: &str, cur: &LiteralCursor<I>| PyError {
+        msg: format!("SyntaxError: f-string: {msg}"),
+        line: cur.line,
+        column: cur.column(),
+    };
+
+    let mut expr = String::new();
+    // nesting of brackets, so e.g. the : in a[1:2] doesn't start the format spec
+    let mut depth = 0;
+    // quote of a string inside the expression
+    let mut in_string: Option<char> = None;
+    let mut conversion = None;
+    let mut spec = Vec::new();
+    loop {
+        // the closing quote of the f-string always ends it, so don't consume it here
+        let ends_literal = cur.peek() == Some(quote) && (!triple || cur.at_quotes(quote, 3));
+        if ends_literal || (!triple && cur.peek() == Some('\n')) {
+            return Err(field_error("expecting '}'", cur));
+        }
+        let Some(c) = cur.next() else {
+            return Err(field_error("expecting '}'", cur));
+        };
+        if let Some(q) = in_string {
+            if c == q {
+                in_string = None;
+            }
+            expr.push(c);
+            continue;
+        }
+        match c {
+            '\'' | '"' => {
+                in_string = Some(c);
+                expr.push(c);
+            }
+            '(' | '[' | '{' => {
+                depth += 1;
+                expr.push(c);
+            }
+            ')' | ']' | '}' if depth > 0 => {
+                depth -= 1;
+                expr.push(c);
+            }
+            '}' => break,
+            '#' => return Err(field_error("expression part cannot include '#'", cur)),
+            // != is still an operator
+            '!' if depth == 0 && cur.peek() != Some('=') => {
+                match cur.next() {
+                    Some(conv @ ('s' | 'r' | 'a')) => conversion = Some(conv),
+                    _ => return Err(field_error("invalid conversion character", cur)),
+                }
+                match cur.next() {
+                    Some('}') => break,
+                    Some(':') => {
+                        spec = build_format_spec(cur, quote, triple)?;
+                        break;
+                    }
+                    _ => return Err(field_error("expecting '}'", cur)),
+                }
+            }
+            ':' if depth == 0 => {
+                spec = build_format_spec(cur, quote, triple)?;
+                break;
+            }
+            _ => expr.push(c),
+        }
+    }
+
+    if expr.trim().is_empty() {
+        return Err(PyError {
+            msg: "SyntaxError: f-string: empty expression not allowed".to_owned(),
+            line: expr_line,
+            column: expr_col,
+        });
+    }
+    let tokens = scan_expression(&expr, expr_idx, expr_line, expr_col)?;
+    Ok(FStringSegment::Field {
+        tokens,
+        conversion,
+        spec,
+    })
+}
+
+// scans the format spec after the : of a replacement field up to the closing }, specs can contain fields themselves
+fn build_format_spec<I: Iterator<Item = char> + Clone>(
+    cur: &mut LiteralCursor<I>,
+    quote: char,
+    triple: bool,
+) -> Result<Vec<FStringSegment>, PyError> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    loop {
+        let ends_literal = cur.peek() == Some(quote) && (!triple || cur.at_quotes(quote, 3));
+        if ends_literal || cur.peek() == Some('\n') || cur.peek().is_none() {
+            return Err(PyError {
+                msg: "SyntaxError: f-string: expecting '}'".to_owned(),
+                line: cur.line,
+                column: cur.column(),
+            });
+        }
+        match cur.next() {
+            Some('}') => break,
+            Some('{') => {
+                if !text.is_empty() {
+                    segments.push(FStringSegment::Literal(std::mem::take(&mut text)));
+                }
+                segments.push(build_fstring_field(cur, quote, triple)?);
+            }
+            Some(c) => text.push(c),
+            None => unreachable!("checked above"),
+        }
+    }
+    if !text.is_empty() {
+        segments.push(FStringSegment::Literal(text));
+    }
+    Ok(segments)
+}
+
+// scans the expression of an f-string field, newlines (only possible in triple quoted strings) are ignored
+// idx is where the expression starts in the code (or in the expression of the field around it)
+fn scan_expression(
+    expr: &str,
+    idx: usize,
+    mut line: u64,
+    mut column: u64,
+) -> Result<Vec<Token>, PyError> {
+    let mut tokens = Vec::new();
+    let mut current_idx = 0;
+    while current_idx < expr.chars().count() {
+        let start = current_idx;
+        match scan_token(expr, &mut current_idx, &mut line, &mut column)? {
+            Some(mut t) => {
+                advance_past(&t, &mut current_idx, &mut line, &mut column);
+                t.span = Span {
+                    start,
+                    end: current_idx,
+                };
+                if t.token_type != TokenType::EndOfLine {
+                    tokens.push(t);
+                }
+            }
+            None => {
+                current_idx += 1;
+                column += 1;
+            }
+        }
+    }
+    tokens.push(empty_token(TokenType::EndOfFile, current_idx, line, column));
+    // the spans were counted from the start of the expression, also the ones of nested fields
+    shift(&mut tokens, idx);
+    Ok(tokens)
+}
+
+// Indent, Dedent and EndOfFile, they take up no chars of the code
+fn empty_token(token_type: TokenType, idx: usize, line: u64, column: u64) -> Token {
+    let mut token = Token::create(token_type, line, column);
+    token.span = Span {
+        start: idx,
+        end: idx,
+    };
+    token
+}
+
+fn shift(tokens: &mut [Token], by: usize) {
+    for t in tokens {
+        t.span.start += by;
+        t.span.end += by;
+        if let TokenType::FString(segments) = &mut t.token_type {
+            for_fields(segments, &mut |tokens| shift(tokens, by));
+        }
+    }
+}
+
+// whether the string literal at idx starts with three quotes after its prefix
+fn triple_quoted(code: &str, idx: usize) -> bool {
+    let mut chars = code
+        .chars()
+        .skip(idx)
+        .skip_while(|c| c.is_ascii_alphabetic());
+    let quote = chars.next();
+    chars.take(2).filter(|&c| Some(c) == quote).count() == 2
+}
+
+// turns char indices into byte offsets, offsets has the one of every char and the length of the code
+fn to_bytes(tokens: &mut [Token], offsets: &[usize]) {
+    for t in tokens {
+        t.span.start = offsets[t.span.start];
+        t.span.end = offsets[t.span.end];
+        if let TokenType::FString(segments) = &mut t.token_type {
+            for_fields(segments, &mut |tokens| to_bytes(tokens, offsets));
+        }
+    }
+}
+
+// the tokens of every field, also the ones in format specs
+fn for_fields(segments: &mut [FStringSegment], f: &mut impl FnMut(&mut [Token])) {
+    for seg in segments {
+        if let FStringSegment::Field { tokens, spec, .. } = seg {
+            f(tokens);
+            for_fields(spec, f);
+        }
+    }
+}
+
+// moves idx, line and col behind the token that was just scanned
+fn advance_past(token: &Token, current_idx: &mut usize, line: &mut u64, column: &mut u64) {
+    // special case because idx and col get updated inside function
+    // (floats: to circumvent weird trimming of trailing zeros by rust, strings: quotes, escapes and newlines)
+    if let TokenType::Float(_) | TokenType::String(_) | TokenType::FString(_) = token.token_type {
+    } else {
+        // move the index by the lexeme length
+        *current_idx += token.value.chars().count();
+        // increase column counter by same amount
+        *column += token.value.chars().count() as u64;
+    }
+    // if it's a EoL token increase the line counter and reset column counter
+    if let TokenType::EndOfLine = token.token_type {
+        *line += 1;
+        *column = 1;
+    }
+}
+
+// checks whether the identifier-like chars are actually the prefix of a string, returns (raw, fmt)
+fn string_prefix(first: char, mut rest: impl Iterator<Item = char>) -> Option<(bool, bool)> {
+    let mut prefix = first.to_ascii_lowercase().to_string();
+    let mut next = rest.next()?;
+    if matches!(next, 'r' | 'R' | 'f' | 'F') {
+        prefix.push(next.to_ascii_lowercase());
+        next = rest.next()?;
+    }
+    if !matches!(next, '"' | '\'') {
+        return None;
+    }
+    match prefix.as_str() {
+        "r" => Some((true, false)),
+        "f" => Some((false, true)),
+        "rf" | "fr" => Some((true, true)),
+        _ => None,
     }
-    Ok(Some(Token::create(TokenType::String(text), line, *column)))
 }
 
 fn build_number(
@@ -392,12 +890,17 @@ fn check_keywords(text: &str) -> Option<TokenType> {
         "not" => Some(TokenType::Not),
         "and" => Some(TokenType::And),
         "or" => Some(TokenType::Or),
+        "in" => Some(TokenType::In),
+        "is" => Some(TokenType::Is),
         "if" => Some(TokenType::If),
         "else" => Some(TokenType::Else),
         "while" => Some(TokenType::While),
+        "with" => Some(TokenType::With),
         "def" => Some(TokenType::Def),
         "return" => Some(TokenType::Return),
-        "print" => Some(TokenType::Print),
+        "import" => Some(TokenType::Import),
+        "from" => Some(TokenType::From),
+        "as" => Some(TokenType::As),
         "None" => Some(TokenType::None),
         _ => None,
     }
@@ -0,0 +1,62 @@
+// standard input and output of the interpreter
+// a host embedding the interpreter can replace them, e.g. to feed input and capture output
+// they are sent to the thread the script runs on, see interpreter::interpret()
+
+use std::io::{self, BufRead, Read, Write};
+
+pub struct Streams {
+    pub stdin: Box<dyn BufRead + Send>,
+    pub stdout: Box<dyn Write + Send>,
+    pub stderr: Box<dyn Write + Send>,
+}
+
+impl Streams {
+    // the real terminal
+    pub fn standard() -> Self {
+        Streams {
+            stdin: Box::new(Stdin {
+                byte: [0],
+                filled: false,
+            }),
+            stdout: Box::new(io::stdout()),
+            stderr: Box::new(io::stderr()),
+        }
+    }
+}
+
+// io::stdin() as a BufRead that can be sent to another thread, unlike StdinLock
+// it takes one byte at a time from the buffer of io::stdin(), so the repl still gets the lines after an input()
+struct Stdin {
+    byte: [u8; 1],
+    // whether byte was read but not consumed yet
+    filled: bool,
+}
+
+impl Read for Stdin {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.filled {
+            return io::stdin().read(buf);
+        }
+        let Some(first) = buf.first_mut() else {
+            return Ok(0);
+        };
+        *first = self.byte[0];
+        self.filled = false;
+        Ok(1)
+    }
+}
+
+impl BufRead for Stdin {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if !self.filled {
+            self.filled = io::stdin().read(&mut self.byte)? == 1;
+        }
+        Ok(if self.filled { &self.byte } else { &[] })
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if amt > 0 {
+            self.filled = false;
+        }
+    }
+}
@@ -0,0 +1,39 @@
+// runs --check on the scripts in tests/check and compares what it prints with the .out file of the same name,
+// the exit code is 1 if it found anything
+
+use std::{fs, path::Path, process::Command};
+
+const BIN: &str = env!("CARGO_BIN_EXE_python_interpreter");
+
+#[test]
+fn problems_are_reported_as_expected() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/check");
+    let mut scripts: Vec<_> = fs::read_dir(&dir)
+        .expect("tests/check can't be read")
+        .map(|entry| entry.expect("directory entry").path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "py"))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty(), "no scripts in tests/check");
+    for script in scripts {
+        let expected_path = script.with_extension("out");
+        let expected = fs::read_to_string(&expected_path)
+            .unwrap_or_else(|e| panic!("can't read {}: {e}", expected_path.display()));
+        let out = Command::new(BIN)
+            .arg("--check")
+            .arg(&script)
+            .output()
+            .expect("the interpreter can't be started");
+        let name = script.display();
+        assert_eq!(
+            String::from_utf8_lossy(&out.stdout),
+            expected,
+            "problems in {name}"
+        );
+        assert_eq!(
+            out.status.code(),
+            Some(i32::from(!expected.is_empty())),
+            "exit code of {name}"
+        );
+    }
+}
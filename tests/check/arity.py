@@ -0,0 +1,10 @@
+def f(a, b):
+    return a + b
+
+
+f()
+f(1)
+f(1, 2, 3)
+f(1, c=3)
+f(1, a=2)
+print(f(1, 2), f(b=1, a=2))
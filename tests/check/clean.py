@@ -0,0 +1,6 @@
+def add(a, b):
+    total = a + b
+    return total
+
+
+print(add(1, 2))
@@ -0,0 +1,25 @@
+early()
+
+
+def early():
+    return 1
+
+
+def f():
+    print(x)
+    x = 1
+    return x
+
+
+def g():
+    h()
+
+
+def h():
+    return 1
+
+
+print(total)
+total = 0
+print(y)
+f()
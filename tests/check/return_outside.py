@@ -0,0 +1,8 @@
+def f():
+    return x
+
+
+x = 1
+if x:
+    return
+return f()
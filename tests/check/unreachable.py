@@ -0,0 +1,17 @@
+def f(x):
+    if x:
+        return 1
+        print("never")
+    else:
+        return 2
+    print("never either")
+
+
+def g():
+    while True:
+        return 1
+        x = 2
+    return 3
+
+
+print(f(True), g())
@@ -0,0 +1,10 @@
+def f(unused_param):
+    unused = 1
+    used = 2
+    assigned_twice = 3
+    assigned_twice = 4
+    return used
+
+
+module_level = 5
+print(f(0))
@@ -0,0 +1,86 @@
+// runs the examples and the scripts in tests/scripts on both engines, with and without the optimizer,
+// every way has to print what the .out file next to the script has, and the errors of its .err file,
+// a script with an .err file ends with an error and exit code 1
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+const BIN: &str = env!("CARGO_BIN_EXE_python_interpreter");
+
+// the options of each way to run a script
+const MODES: [&[&str]; 4] = [
+    &[],
+    &["--no-optimize"],
+    &["--tree-walker"],
+    &["--tree-walker", "--no-optimize"],
+];
+
+// the .py files of a directory of the project, sorted
+fn scripts(dir: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("can't read {}: {e}", dir.display()))
+        .map(|entry| entry.expect("directory entry").path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "py"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+// without input and arguments, a script reading them gets the same on every engine
+fn run(options: &[&str], script: &Path) -> Output {
+    Command::new(BIN)
+        .args(options)
+        .arg(script)
+        .stdin(Stdio::null())
+        .output()
+        .expect("the interpreter can't be started")
+}
+
+// the file next to the script with the given extension, empty if there is none
+fn expected(script: &Path, extension: &str) -> String {
+    fs::read_to_string(script.with_extension(extension)).unwrap_or_default()
+}
+
+fn assert_as_expected_everywhere(dir: &str) {
+    let scripts = scripts(dir);
+    assert!(!scripts.is_empty(), "no scripts in {dir}");
+    for script in scripts {
+        assert!(
+            script.with_extension("out").exists(),
+            "{} has no .out file",
+            script.display()
+        );
+        let stdout = expected(&script, "out");
+        let stderr = expected(&script, "err");
+        let code = i32::from(!stderr.is_empty());
+        for options in MODES {
+            let out = run(options, &script);
+            let what = format!("{} with {options:?}", script.display());
+            assert_eq!(out.status.code(), Some(code), "exit code of {what}");
+            assert_eq!(
+                String::from_utf8_lossy(&out.stdout),
+                stdout,
+                "output of {what}"
+            );
+            assert_eq!(
+                String::from_utf8_lossy(&out.stderr),
+                stderr,
+                "errors of {what}"
+            );
+        }
+    }
+}
+
+#[test]
+fn examples_run_as_expected_everywhere() {
+    assert_as_expected_everywhere("examples");
+}
+
+#[test]
+fn test_scripts_run_as_expected_everywhere() {
+    assert_as_expected_everywhere("tests/scripts");
+}
@@ -0,0 +1,51 @@
+// formats the files in tests/fmt/input and compares them with the ones of the same name in tests/fmt/expected,
+// formatting the result again must not change it anymore
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const BIN: &str = env!("CARGO_BIN_EXE_python_interpreter");
+
+// fmt changes the file in place, so it runs on a copy
+fn format(source: &Path, name: &str) -> String {
+    let copy = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::copy(source, &copy).expect("the file can't be copied");
+    let out = Command::new(BIN)
+        .arg("fmt")
+        .arg(&copy)
+        .output()
+        .expect("the interpreter can't be started");
+    assert!(
+        out.status.success(),
+        "formatting {name} failed: {}{}",
+        String::from_utf8_lossy(&out.stdout),
+        String::from_utf8_lossy(&out.stderr)
+    );
+    fs::read_to_string(&copy).expect("the formatted file can't be read")
+}
+
+#[test]
+fn files_are_formatted_as_expected() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fmt");
+    let mut inputs: Vec<PathBuf> = fs::read_dir(dir.join("input"))
+        .expect("tests/fmt/input can't be read")
+        .map(|entry| entry.expect("directory entry").path())
+        .collect();
+    inputs.sort();
+    assert!(!inputs.is_empty(), "no files in tests/fmt/input");
+    for input in inputs {
+        let name = input.file_name().expect("file name").to_string_lossy();
+        let expected_path = dir.join("expected").join(name.as_ref());
+        let expected = fs::read_to_string(&expected_path)
+            .unwrap_or_else(|e| panic!("can't read {}: {e}", expected_path.display()));
+        assert_eq!(format(&input, &name), expected, "formatting {name}");
+        assert_eq!(
+            format(&expected_path, &name),
+            expected,
+            "formatting {name} a second time"
+        );
+    }
+}
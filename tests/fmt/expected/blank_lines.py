@@ -0,0 +1,17 @@
+x = 1
+
+
+y = 2
+
+
+def f():
+    a = 1
+
+    b = 2
+
+    return a
+class_like = 3
+if x:
+    z = 1
+
+    z = 2
@@ -0,0 +1,22 @@
+#!/usr/bin/env python
+# comment without a space
+import math  # after code
+
+
+def f(x):
+    # before the first statement
+    if x:
+        y = 1
+        # at the end of the if block
+    # dedented, belongs to the function again
+    return x
+    # at the end of the function
+# dedented to the top level
+
+
+while False:
+    x = 1
+    # indented deeper than the block
+#
+print(f(1))
+# at the end of the file
@@ -0,0 +1,12 @@
+print('single', "double", end="\n")
+s = 'it\'s'
+t = "say \"hi\""
+u = 'tab\tand\\backslash\r\x01'
+name = 'x'
+v = f'{name!r:>10} and {"nested"} {s + "x"}\n'
+w = f'{name}' + f"{{braces}}"
+doc = """first line
+second "line" \\ end"""
+short = '''one line'''
+r = r'raw\n'
+print(s, t, u, v, w, doc, short, r)
@@ -0,0 +1,9 @@
+short = [1,2,3]
+numbers = [1000000, 2000000, 3000000, 4000000, 5000000, 6000000, 7000000, 8000000, 9000000]
+words = ["a very long string number one", "a very long string number two", "a very long string number three"]
+print("some text that is quite long", "more text that is quite long", sep=" -- ", end="\n")
+def f():
+    return [[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], [11, 12, 13, 14, 15, 16, 17, 18, 19, 20], [21, 22]]
+x = (1+2)*-3
+y = not x==1 and x<2<=3
+print(numbers[1:3], words[::2], f()[0][1])
@@ -0,0 +1,56 @@
+# nested functions sharing variables through cells, and cycles for the collector
+import gc
+
+
+def counter(start):
+    count = start
+
+    def step(by):
+        total = count + by
+        return total
+
+    return step
+
+
+def make_adder(n):
+    def add(x):
+        return x + n
+
+    return add
+
+
+def recurse(n):
+    if n == 0:
+        return []
+    rest = recurse(n - 1)
+    rest.append(n)
+    return rest
+
+
+step = counter(10)
+print(step(5))
+add3 = make_adder(3)
+sub1 = make_adder(-1)
+print(add3(4), sub1(1))
+print(recurse(5))
+
+a = [1]
+a.append(a)
+print(a)
+b = [a]
+a = 0
+b = 0
+print(gc.collect() > 0)
+
+
+def outer():
+    def loop():
+        return loop
+
+    return loop
+
+
+f = outer()
+print(f() == f, f)
+f = 0
+print(gc.collect() > 0)
@@ -0,0 +1,8 @@
+# output before a runtime error has to be the same, as well as the error
+def get(xs, i):
+    return xs[i]
+
+
+print(get([1, 2, 3], 1))
+print(get([1, 2, 3], 5))
+print("never")
@@ -0,0 +1,6 @@
+# imported by importer.py
+greeting = "hi"
+
+
+def greet(name):
+    return greeting + " " + name
@@ -0,0 +1,5 @@
+# imports a module next to the script
+import helper
+from helper import greet
+
+print(helper.greet("a"), greet("b"), helper.greeting)
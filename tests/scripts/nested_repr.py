@@ -0,0 +1,10 @@
+# lists containing themselves are printed as [...], lists nested deeper than the recursion limit are an error
+a = [1]
+a.append(a)
+print(a, f"{a!r}", "{}".format(a))
+b = []
+i = 0
+while i < 1500:
+    b = [b]
+    i = i + 1
+print(str(b))
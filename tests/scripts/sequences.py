@@ -0,0 +1,20 @@
+# slices, string and list methods and comparisons
+xs = [5, 3, 8, 1, 9, 2]
+print(xs[1:4], xs[::-1], xs[::2], xs[-2:], xs[10:], xs[4:1:-1])
+s = "hello world"
+print(s[::-1], s[2:7], s[-5:])
+print(s.split(" "), "a,b,,c".split(",", 1), s.replace("o", "0", 1), s.find("o", 5))
+print(s.upper(), "  x ".strip(), "-".join(["a", "b", "c"]), s.startswith("he"))
+ys = [3, 1, 2]
+ys.sort()
+ys.append(4)
+ys.insert(0, 0)
+print(ys, ys.index(2), ys.count(4), ys.pop(), ys)
+ys[1:3] = [7, 7, 7]
+print(ys)
+print(1 == 1.0, 2 < 2.5, [1, 2] < [1, 3], "a" < "b", [1, [2]] == [1, [2]])
+print(9007199254740993 == 9007199254740992.0, 9007199254740993 > 9007199254740992.0)
+n = 7
+print(f"{n} squared is {n * n}, {s!r}, {3.14159:.2f}, {n:>4}")
+print(3 in xs, 4 not in xs, "lo" in s)
+print(7 / 2, 2 * 3 + 4, -2.5 * 2, 10 - 3 - 2)